@@ -24,12 +24,18 @@ fn main() {
         println!("cargo:rustc-cfg=rtsan_supported");
     }
 
+    // reported by `status()`
+    println!("cargo:rustc-env=RTSAN_TARGET={target}");
+
     if std::env::var(RTSAN_ENV_VAR).is_ok() {
         if is_supported {
             println!("cargo:warning=RealtimeSanitizer enabled");
             println!("cargo:rustc-cfg=rtsan_enabled");
         } else {
-            println!("cargo:error=Realtime Sanitizer not supported on target: {target}");
+            println!(
+                "cargo:warning=RealtimeSanitizer is not supported on target {target}, \
+                 sanitizing is disabled"
+            );
         }
     }
 }
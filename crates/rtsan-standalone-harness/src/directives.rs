@@ -0,0 +1,186 @@
+use rtsan_standalone::report::{self, Report};
+
+/// The expectations and the setup of a test, read from the `//` comments
+/// at the top of its source file.
+///
+/// See the README for the directives.
+///
+/// `kind`, `function` and `frame` must all match the same report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Directives {
+    pub expect: Expect,
+    pub checks: Vec<Check>,
+    pub kind: Option<String>,
+    pub function: Option<String>,
+    pub frames: Vec<String>,
+    pub options: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+}
+
+/// Whether a test expects a violation, see [`Directives`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Expect {
+    #[default]
+    Violation,
+    Clean,
+}
+
+/// A `check` or `check-not` directive, see [`Directives`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Contains(String),
+    NotContains(String),
+}
+
+impl Directives {
+    /// Reads the directives from the leading `//` comments of `source`.
+    /// Comments that are not of the form `// name: value` with a lowercase
+    /// name are skipped, an unknown name is an error.
+    pub fn parse(source: &str) -> Result<Directives, String> {
+        let mut directives = Directives::default();
+        let comments = source
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.starts_with("//"));
+        for comment in comments {
+            let comment = comment.trim_start_matches('/').trim();
+            let Some((name, value)) = comment.split_once(": ") else {
+                continue;
+            };
+            if !name.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+                continue;
+            }
+            let value = value.trim();
+            match name {
+                "check" => directives.checks.push(Check::Contains(value.into())),
+                "check-not" => directives.checks.push(Check::NotContains(value.into())),
+                "expect" => {
+                    directives.expect = match value {
+                        "violation" => Expect::Violation,
+                        "clean" => Expect::Clean,
+                        _ => {
+                            return Err(format!(
+                                "unknown expectation `{value}`, expected `violation` or `clean`"
+                            ))
+                        }
+                    }
+                }
+                "kind" => directives.kind = Some(value.into()),
+                "function" => directives.function = Some(value.into()),
+                "frame" => directives.frames.push(value.into()),
+                "options" => directives.options.push(value.into()),
+                "env" => {
+                    let Some((name, value)) = value.split_once('=') else {
+                        return Err(format!("expected `// env: NAME=value`, got `{value}`"));
+                    };
+                    directives.env.push((name.into(), value.into()));
+                }
+                "args" => directives
+                    .args
+                    .extend(value.split_whitespace().map(String::from)),
+                _ => return Err(format!("unknown directive `// {name}:`")),
+            }
+        }
+        if directives.expect == Expect::Clean && directives.expects_report() {
+            return Err(String::from(
+                "`kind`, `function` and `frame` can not be used with `// expect: clean`",
+            ));
+        }
+        Ok(directives)
+    }
+
+    /// The value of `RTSAN_OPTIONS` for the test, `None` without `options`.
+    pub fn rtsan_options(&self) -> Option<String> {
+        (!self.options.is_empty()).then(|| self.options.join(":"))
+    }
+
+    /// Checks the output of a test, `success` is whether its process exited
+    /// successfully.
+    pub fn verify(&self, success: bool, stderr: &str) -> Result<(), String> {
+        let reports = report::parse(stderr);
+        match self.expect {
+            Expect::Violation if reports.is_empty() => {
+                return Err(format!("no violation detected. output:\n{stderr}"));
+            }
+            Expect::Clean if !reports.is_empty() || !success => {
+                return Err(format!("expected a clean run. output:\n{stderr}"));
+            }
+            _ => {}
+        }
+        if self.expects_report() && !reports.iter().any(|report| self.matches(report)) {
+            return Err(format!(
+                "no report matches{}. output:\n{stderr}",
+                self.describe_report()
+            ));
+        }
+        self.verify_checks(stderr)
+    }
+
+    fn expects_report(&self) -> bool {
+        self.kind.is_some() || self.function.is_some() || !self.frames.is_empty()
+    }
+
+    fn matches(&self, report: &Report) -> bool {
+        self.kind
+            .as_ref()
+            .map_or(true, |kind| report.kind.to_string() == *kind)
+            && self
+                .function
+                .as_ref()
+                .map_or(true, |function| report.function == *function)
+            && self.frames.iter().all(|text| {
+                report
+                    .frames
+                    .iter()
+                    .any(|frame| frame.raw.contains(text.as_str()))
+            })
+    }
+
+    fn describe_report(&self) -> String {
+        let mut description = String::new();
+        if let Some(kind) = &self.kind {
+            description += &format!(" kind `{kind}`");
+        }
+        if let Some(function) = &self.function {
+            description += &format!(" function `{function}`");
+        }
+        for frame in &self.frames {
+            description += &format!(" frame `{frame}`");
+        }
+        description
+    }
+
+    /// Matches the checks in order, each `check-not` covers the output
+    /// between the previous and the next `check`.
+    fn verify_checks(&self, stderr: &str) -> Result<(), String> {
+        let mut start = 0;
+        let mut excluded: Vec<&str> = Vec::new();
+        for check in &self.checks {
+            match check {
+                Check::NotContains(text) => excluded.push(text),
+                Check::Contains(text) => {
+                    let Some(offset) = stderr[start..].find(text.as_str()) else {
+                        return Err(format!(
+                            "wrong detection output. expected: \n{text}\ngot:\n{stderr}"
+                        ));
+                    };
+                    check_excluded(&excluded, &stderr[start..start + offset], stderr)?;
+                    excluded.clear();
+                    start += offset + text.len();
+                }
+            }
+        }
+        check_excluded(&excluded, &stderr[start..], stderr)
+    }
+}
+
+fn check_excluded(excluded: &[&str], region: &str, stderr: &str) -> Result<(), String> {
+    match excluded.iter().find(|text| region.contains(**text)) {
+        Some(text) => Err(format!(
+            "wrong detection output. not expected: \n{text}\ngot:\n{stderr}"
+        )),
+        None => Ok(()),
+    }
+}
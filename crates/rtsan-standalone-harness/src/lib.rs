@@ -0,0 +1,176 @@
+#![doc = include_str!("../README.md")]
+
+mod directives;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::Arc;
+use std::{env, fs};
+
+use libtest_mimic::{Arguments, Failed, Trial};
+use rtsan_standalone::{status, Status};
+
+pub use directives::{Check, Directives, Expect};
+
+/// Runs every binary of a package as a test, checking its output against the
+/// [`Directives`] in its source file.
+#[derive(Debug, Clone)]
+pub struct Harness {
+    package: String,
+    bins: PathBuf,
+    release: bool,
+}
+
+/// A source file in the directory of the binaries.
+struct Test {
+    name: String,
+    /// The error if the directives can not be read.
+    directives: Result<Directives, String>,
+}
+
+impl Harness {
+    /// Tests the binaries of `package` with their sources in `bins`, usually
+    /// the `src/bin` directory of the package, relative to the directory
+    /// `cargo test` runs the test in.
+    pub fn new(package: impl Into<String>, bins: impl Into<PathBuf>) -> Self {
+        Harness {
+            package: package.into(),
+            bins: bins.into(),
+            release: false,
+        }
+    }
+
+    /// Builds the binaries with the release profile.
+    pub fn release(mut self, release: bool) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Builds all binaries once with `RTSAN_ENABLE=1` and runs the tests
+    /// selected by the command line arguments. The tests are ignored on
+    /// targets RTSan does not support.
+    pub fn run(self) -> ExitCode {
+        let args = Arguments::from_args();
+
+        let mut tests = match self.collect() {
+            Ok(tests) => tests,
+            Err(error) => {
+                eprintln!("error: {error}");
+                return ExitCode::FAILURE;
+            }
+        };
+        tests.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let ignored = matches!(status(), Status::UnsupportedTarget(_));
+        if ignored {
+            println!("WARNING: RTSAN not supported. Skipping detection tests");
+        }
+
+        let executables = if ignored || args.list {
+            Ok(HashMap::new())
+        } else {
+            self.build()
+        };
+        let executables = Arc::new(executables);
+
+        let trials = tests
+            .into_iter()
+            .map(|test| {
+                let executables = Arc::clone(&executables);
+                let name = test.name.clone();
+                Trial::test(test.name, move || {
+                    let directives = test.directives?;
+                    let executables = executables.as_ref().as_ref()?;
+                    let executable = executables
+                        .get(&name)
+                        .ok_or_else(|| format!("no binary `{name}` was built"))?;
+                    run_test(executable, &directives)
+                })
+                .with_ignored_flag(ignored)
+            })
+            .collect();
+
+        libtest_mimic::run(&args, trials).exit_code()
+    }
+
+    /// The source files in `bins`.
+    fn collect(&self) -> Result<Vec<Test>, String> {
+        let entries = fs::read_dir(&self.bins)
+            .map_err(|error| format!("can not read `{}`: {error}", self.bins.display()))?;
+        let mut tests = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|error| error.to_string())?.path();
+            if path.extension().map_or(true, |extension| extension != "rs") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let directives = fs::read_to_string(&path)
+                .map_err(|error| format!("can not read `{}`: {error}", path.display()))
+                .and_then(|source| Directives::parse(&source));
+            tests.push(Test {
+                name: name.to_owned(),
+                directives,
+            });
+        }
+        Ok(tests)
+    }
+
+    /// Builds all binaries of the package and returns their paths by name.
+    fn build(&self) -> Result<HashMap<String, PathBuf>, String> {
+        let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+        let mut command = Command::new(cargo);
+        command
+            .args([
+                "build",
+                "--bins",
+                "--message-format=json-render-diagnostics",
+            ])
+            .args(["--package", &self.package])
+            .env("RTSAN_ENABLE", "1")
+            .stderr(Stdio::inherit());
+        if self.release {
+            command.arg("--release");
+        }
+        let output = command
+            .output()
+            .map_err(|error| format!("can not run cargo: {error}"))?;
+        if !output.status.success() {
+            return Err(format!("building `{}` failed", self.package));
+        }
+
+        let mut executables = HashMap::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            let name = message["target"]["name"].as_str();
+            let executable = message["executable"].as_str();
+            if let (Some(name), Some(executable)) = (name, executable) {
+                executables.insert(name.to_owned(), PathBuf::from(executable));
+            }
+        }
+        Ok(executables)
+    }
+}
+
+fn run_test(executable: &Path, directives: &Directives) -> Result<(), Failed> {
+    let mut command = Command::new(executable);
+    command
+        .args(&directives.args)
+        .envs(directives.env.iter().map(|(name, value)| (name, value)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(options) = directives.rtsan_options() {
+        command.env("RTSAN_OPTIONS", options);
+    }
+    let output = command
+        .output()
+        .map_err(|error| format!("can not run `{}`: {error}", executable.display()))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    directives.verify(output.status.success(), &stderr)?;
+    Ok(())
+}
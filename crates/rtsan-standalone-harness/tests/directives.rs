@@ -0,0 +1,111 @@
+use rtsan_standalone_harness::{Check, Directives, Expect};
+
+const OUTPUT: &str = "\
+starting
+==1==ERROR: RealtimeSanitizer: unsafe-library-call
+Intercepted call to real-time unsafe function `malloc` in real-time context!
+    #0 0x10 in malloc rtsan_interceptors_posix.cpp:470:34
+    #1 0x20 in vector::process::h1f2e src/bin/vector.rs:7:13
+SUMMARY: RealtimeSanitizer: unsafe-library-call
+";
+
+#[test]
+fn test_parse() {
+    let directives = Directives::parse(
+        "// check: first
+// check-not: second
+// Note: not a directive
+// options: halt_on_error=false
+// options: verbosity=1
+// env: RTSAN_ACTIVE=1
+// args: --fast  --once
+// kind: unsafe-library-call
+// function: malloc
+// frame: vector::process
+use rtsan_standalone::*;
+// check: after the header
+",
+    )
+    .unwrap();
+    assert_eq!(directives.expect, Expect::Violation);
+    assert_eq!(
+        directives.checks,
+        [
+            Check::Contains("first".into()),
+            Check::NotContains("second".into())
+        ]
+    );
+    assert_eq!(
+        directives.rtsan_options().as_deref(),
+        Some("halt_on_error=false:verbosity=1")
+    );
+    assert_eq!(directives.env, [("RTSAN_ACTIVE".into(), "1".into())]);
+    assert_eq!(directives.args, ["--fast", "--once"]);
+    assert_eq!(directives.kind.as_deref(), Some("unsafe-library-call"));
+    assert_eq!(directives.function.as_deref(), Some("malloc"));
+    assert_eq!(directives.frames, ["vector::process"]);
+
+    assert_eq!(
+        Directives::parse("// expect: clean\n").unwrap().expect,
+        Expect::Clean
+    );
+    assert!(Directives::parse("// chek: typo\n").is_err());
+    assert!(Directives::parse("// expect: maybe\n").is_err());
+    assert!(Directives::parse("// env: RTSAN_ACTIVE\n").is_err());
+    assert!(Directives::parse("// expect: clean\n// function: malloc\n").is_err());
+}
+
+#[test]
+fn test_verify() {
+    let verify = |source: &str, success: bool, output: &str| {
+        Directives::parse(source)
+            .unwrap()
+            .verify(success, output)
+            .is_ok()
+    };
+
+    assert!(verify("// check: malloc\n", false, OUTPUT));
+    assert!(!verify("// check: malloc\n", true, "clean output"));
+    assert!(verify("// expect: clean\n", true, "clean output"));
+    assert!(!verify("// expect: clean\n", true, OUTPUT));
+    assert!(!verify("// expect: clean\n", false, "crashed"));
+
+    // checks match in order
+    assert!(verify(
+        "// check: starting\n// check: SUMMARY\n",
+        false,
+        OUTPUT
+    ));
+    assert!(!verify(
+        "// check: SUMMARY\n// check: starting\n",
+        false,
+        OUTPUT
+    ));
+
+    // `check-not` covers the output between the surrounding checks
+    assert!(verify(
+        "// check: starting\n// check-not: #1\n// check: #0\n",
+        false,
+        OUTPUT
+    ));
+    assert!(!verify(
+        "// check: starting\n// check-not: #0\n// check: #1\n",
+        false,
+        OUTPUT
+    ));
+    assert!(!verify(
+        "// check: #0\n// check-not: SUMMARY\n",
+        false,
+        OUTPUT
+    ));
+
+    // report fields
+    assert!(verify(
+        "// kind: unsafe-library-call\n// function: malloc\n// frame: vector::process\n",
+        false,
+        OUTPUT
+    ));
+    assert!(!verify("// kind: blocking-call\n", false, OUTPUT));
+    assert!(!verify("// function: calloc\n", false, OUTPUT));
+    assert!(!verify("// frame: vector::main\n", false, OUTPUT));
+}
@@ -0,0 +1,221 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    meta::ParseNestedMeta, spanned::Spanned, Block, Expr, ExprLit, FnArg, ForeignItem,
+    ForeignItemFn, GenericParam, Ident, ItemForeignMod, Lit, LitStr, Meta, Pat, Path, Signature,
+    Visibility,
+};
+
+use crate::crate_path::CratePath;
+use crate::item::{contains_self, skip, Scope, Transform};
+
+/// Arguments of the `blocking` attribute.
+#[derive(Default)]
+pub(crate) struct Blocking {
+    name: Option<LitStr>,
+    reason: Option<LitStr>,
+    /// Set by `safe`, makes the wrappers of an `extern` block safe.
+    safe: Option<Ident>,
+    krate: CratePath,
+}
+
+impl Blocking {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("reason") {
+            self.reason = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("safe") {
+            self.safe = meta.path.get_ident().cloned();
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            self.krate.parse(&meta)
+        } else {
+            Err(meta.error(
+                "unsupported blocking argument, expected `name`, `reason`, `safe` or `crate`",
+            ))
+        }
+    }
+
+    /// Expressions that concatenated give the name reported to RTSan.
+    fn name_parts(&self, scope: &Scope, sig: &Signature) -> Vec<TokenStream> {
+        let mut parts = Vec::new();
+        if let Some(name) = &self.name {
+            parts.push(name.to_token_stream());
+        } else {
+            let ident = sig.ident.to_string();
+            if scope.self_ty.is_some() || sig.receiver().is_some() || mentions_self(sig) {
+                parts.push(quote!(::core::any::type_name::<Self>()));
+            } else {
+                parts.push(quote!(::core::module_path!()));
+            }
+            parts.push(quote!("::"));
+            parts.push(quote!(#ident));
+
+            let type_params: Vec<_> = sig
+                .generics
+                .params
+                .iter()
+                .filter_map(|param| match param {
+                    GenericParam::Type(param) => Some(&param.ident),
+                    _ => None,
+                })
+                .collect();
+            for (i, param) in type_params.iter().enumerate() {
+                parts.push(if i == 0 { quote!("<") } else { quote!(", ") });
+                parts.push(quote!(::core::any::type_name::<#param>()));
+            }
+            if !type_params.is_empty() {
+                parts.push(quote!(">"));
+            }
+        }
+        self.push_reason(&mut parts);
+        parts
+    }
+
+    fn push_reason(&self, parts: &mut Vec<TokenStream>) {
+        if let Some(reason) = &self.reason {
+            parts.push(quote!(" ("));
+            parts.push(reason.to_token_stream());
+            parts.push(quote!(")"));
+        }
+    }
+}
+
+impl Transform for Blocking {
+    fn transform(&self, scope: &Scope, sig: &Signature, block: Block) -> syn::Result<Block> {
+        if let Some(safe) = &self.safe {
+            return Err(syn::Error::new(
+                safe.span(),
+                "`safe` is only supported on an `extern` block",
+            ));
+        }
+        let krate = self.crate_path()?;
+        let parts = self.name_parts(scope, sig);
+
+        Ok(syn::parse_quote!({
+            #krate::__private::notify_blocking_call(&[#(#parts),*]);
+            // Directly execute and return the block
+            #block
+        }))
+    }
+
+    fn crate_path(&self) -> syn::Result<Path> {
+        self.krate.get()
+    }
+}
+
+/// Returns `true` if `Self` appears in the parameters or the return type,
+/// which means the function is an associated function.
+fn mentions_self(sig: &Signature) -> bool {
+    contains_self(sig.inputs.to_token_stream()) || contains_self(sig.output.to_token_stream())
+}
+
+/// Wraps every function of an `extern` block. The foreign function is renamed
+/// and keeps its symbol through `#[link_name]`, while a wrapper with the
+/// original name and visibility reports the symbol name and forwards the call.
+/// The wrapper is `unsafe` unless the attribute has `safe`.
+pub(crate) fn expand_foreign(
+    mut foreign_mod: ItemForeignMod,
+    args: &Blocking,
+) -> syn::Result<TokenStream> {
+    if let Some(name) = &args.name {
+        return Err(syn::Error::new(
+            name.span(),
+            "`name` can not be used on an `extern` block, functions are reported by their symbol",
+        ));
+    }
+    let mut wrappers = Vec::new();
+    for foreign_item in &mut foreign_mod.items {
+        let ForeignItem::Fn(function) = foreign_item else {
+            continue;
+        };
+        if skip(&mut function.attrs) {
+            continue;
+        }
+        if let Some(variadic) = &function.sig.variadic {
+            return Err(syn::Error::new(
+                variadic.span(),
+                "variadic functions can not be wrapped, use `#[rtsan(skip)]` to leave them out",
+            ));
+        }
+        wrappers.push(foreign_wrapper(function, args)?);
+    }
+    Ok(quote!(#foreign_mod #(#wrappers)*))
+}
+
+fn foreign_wrapper(function: &mut ForeignItemFn, args: &Blocking) -> syn::Result<TokenStream> {
+    let symbol = match function
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("link_name"))
+    {
+        Some(attr) => match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(symbol),
+                    ..
+                }) => symbol.clone(),
+                value => return Err(syn::Error::new(value.span(), "expected a string literal")),
+            },
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "expected `link_name = \"...\"`",
+                ))
+            }
+        },
+        None => {
+            let symbol = LitStr::new(&function.sig.ident.to_string(), function.sig.ident.span());
+            function
+                .attrs
+                .push(syn::parse_quote!(#[link_name = #symbol]));
+            symbol
+        }
+    };
+
+    let mut sig = function.sig.clone();
+    sig.unsafety = match args.safe {
+        Some(_) => None,
+        None => Some(Default::default()),
+    };
+    let mut call_args = Vec::new();
+    for (i, input) in sig.inputs.iter_mut().enumerate() {
+        if let FnArg::Typed(pat_type) = input {
+            if !matches!(*pat_type.pat, Pat::Ident(_)) {
+                let arg = format_ident!("__arg{i}");
+                *pat_type.pat = syn::parse_quote!(#arg);
+            }
+            call_args.push(pat_type.pat.clone());
+        }
+    }
+
+    let foreign = format_ident!("__rtsan_{}", function.sig.ident);
+    function.sig.ident = foreign.clone();
+    let vis = std::mem::replace(&mut function.vis, Visibility::Inherited);
+    // documentation belongs to the wrapper, `cfg` to both
+    let (docs, attrs): (Vec<_>, Vec<_>) = function
+        .attrs
+        .drain(..)
+        .partition(|attr| attr.path().is_ident("doc"));
+    function.attrs = attrs;
+    let cfgs = function
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"));
+
+    let krate = args.crate_path()?;
+    let mut parts = vec![symbol.to_token_stream()];
+    args.push_reason(&mut parts);
+    Ok(quote! {
+        #(#docs)*
+        #(#cfgs)*
+        #[inline]
+        #vis #sig {
+            #krate::__private::notify_blocking_call(&[#(#parts),*]);
+            unsafe { #foreign(#(#call_args),*) }
+        }
+    })
+}
@@ -0,0 +1,166 @@
+use std::{cell::RefCell, env, fs, path::PathBuf, time::SystemTime};
+
+use proc_macro2::Span;
+use syn::{meta::ParseNestedMeta, Path};
+use toml::{Table, Value};
+
+/// The `crate = path` argument of all macros, the path of the
+/// `rtsan-standalone` crate in generated code.
+#[derive(Default)]
+pub(crate) struct CratePath(Option<Path>);
+
+impl CratePath {
+    pub(crate) fn parse(&mut self, meta: &ParseNestedMeta) -> syn::Result<()> {
+        self.0 = Some(meta.value()?.parse()?);
+        Ok(())
+    }
+
+    pub(crate) fn set(&mut self, path: Path) {
+        self.0 = Some(path);
+    }
+
+    /// The given path, or the crate-level default.
+    pub(crate) fn get(&self) -> syn::Result<Path> {
+        match &self.0 {
+            Some(path) => Ok(path.clone()),
+            None => default_path(),
+        }
+    }
+}
+
+/// The manifests a default was read from, with their modification times.
+type Manifests = Vec<(PathBuf, Option<SystemTime>)>;
+
+thread_local! {
+    /// The default path per manifest and the modification times of the
+    /// manifests it was read from, a proc macro server can expand multiple
+    /// crates and outlive edits of the manifests.
+    static DEFAULTS: RefCell<Vec<(String, Manifests, String)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Reads the crate-level default from the `Cargo.toml` of the crate being
+/// compiled: `crate` in the `[package.metadata.rtsan-standalone]` table, or
+/// the name of a renamed `rtsan-standalone` dependency, also if it is renamed
+/// in `[workspace.dependencies]`. Falls back to `::rtsan_standalone`.
+fn default_path() -> syn::Result<Path> {
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = DEFAULTS.with(|defaults| {
+        let mut defaults = defaults.borrow_mut();
+        if let Some((_, _, path)) = defaults.iter().find(|(cached, manifests, _)| {
+            *cached == dir
+                && manifests
+                    .iter()
+                    .all(|(manifest, time)| time.is_some() && modified(manifest) == *time)
+        }) {
+            return path.clone();
+        }
+        let mut manifests = Vec::new();
+        let path = read(PathBuf::from(&dir).join("Cargo.toml"), &mut manifests)
+            .and_then(|manifest| {
+                configured(&manifest, || {
+                    workspace_root(&dir, &manifest).and_then(|root| read(root, &mut manifests))
+                })
+            })
+            .unwrap_or_else(|| String::from("::rtsan_standalone"));
+        defaults.retain(|(cached, _, _)| *cached != dir);
+        defaults.push((dir, manifests, path.clone()));
+        path
+    });
+    syn::parse_str(&path).map_err(|_| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("`crate = \"{path}\"` in `[package.metadata.rtsan-standalone]` is not a path"),
+        )
+    })
+}
+
+fn modified(manifest: &std::path::Path) -> Option<SystemTime> {
+    fs::metadata(manifest).and_then(|m| m.modified()).ok()
+}
+
+/// Parses a manifest, recording its modification time in `manifests`.
+fn read(manifest: PathBuf, manifests: &mut Manifests) -> Option<Table> {
+    manifests.push((manifest.clone(), modified(&manifest)));
+    fs::read_to_string(&manifest).ok()?.parse().ok()
+}
+
+/// The manifest of the workspace the crate in `dir` belongs to: the one
+/// `package.workspace` points to, or the closest one with a `[workspace]`
+/// table, starting with the manifest of the crate itself.
+fn workspace_root(dir: &str, manifest: &Table) -> Option<PathBuf> {
+    if manifest.contains_key("workspace") {
+        return Some(PathBuf::from(dir).join("Cargo.toml"));
+    }
+    let configured = manifest
+        .get("package")
+        .and_then(|package| package.get("workspace"))
+        .and_then(Value::as_str);
+    if let Some(root) = configured {
+        return Some(PathBuf::from(dir).join(root).join("Cargo.toml"));
+    }
+    PathBuf::from(dir)
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| {
+            fs::read_to_string(manifest)
+                .ok()
+                .and_then(|manifest| manifest.parse::<Table>().ok())
+                .is_some_and(|manifest| manifest.contains_key("workspace"))
+        })
+}
+
+/// The `crate` key of the metadata, or the name of the dependency whose
+/// `package` is `rtsan-standalone`, in any dependency table including the
+/// target specific ones. A dependency with `workspace = true` is looked up
+/// in the `[workspace.dependencies]` of the manifest returned by `workspace`.
+fn configured(manifest: &Table, workspace: impl FnOnce() -> Option<Table>) -> Option<String> {
+    let metadata = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("rtsan-standalone"))
+        .and_then(|metadata| metadata.get("crate"))
+        .and_then(Value::as_str);
+    if let Some(path) = metadata {
+        return Some(path.to_owned());
+    }
+    let is_rtsan = |dependency: &Value| {
+        dependency.get("package").and_then(Value::as_str) == Some("rtsan-standalone")
+    };
+    let targets = manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(Value::as_table));
+    let mut workspace = Some(workspace);
+    let mut shared = None;
+    std::iter::once(manifest)
+        .chain(targets)
+        .flat_map(|table| {
+            ["dependencies", "dev-dependencies", "build-dependencies"]
+                .into_iter()
+                .filter_map(|key| table.get(key).and_then(Value::as_table))
+        })
+        .flat_map(|dependencies| dependencies.iter())
+        .find(|(name, dependency)| {
+            if dependency.get("workspace").and_then(Value::as_bool) != Some(true) {
+                return is_rtsan(dependency);
+            }
+            // the workspace manifest is only read for inherited dependencies
+            let shared: &Table = shared.get_or_insert_with(|| {
+                workspace
+                    .take()
+                    .and_then(|workspace| workspace())
+                    .and_then(|mut workspace| workspace.remove("workspace"))
+                    .and_then(|mut workspace| workspace.as_table_mut()?.remove("dependencies"))
+                    .and_then(|dependencies| match dependencies {
+                        Value::Table(dependencies) => Some(dependencies),
+                        _ => None,
+                    })
+                    .unwrap_or_default()
+            });
+            shared.get(name.as_str()).is_some_and(is_rtsan)
+        })
+        .map(|(name, _)| format!("::{}", name.replace('-', "_")))
+}
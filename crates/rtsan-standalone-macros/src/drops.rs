@@ -0,0 +1,486 @@
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Attribute, Block, Expr, ExprCall, ExprPath, FnArg, Item, LitStr, Local, Macro, Pat, PatIdent,
+    Path, Signature, Stmt,
+};
+
+use crate::item::is_rtsan_attr;
+use crate::lint::{stmt_attrs_mut, warning};
+
+/// Where the locals of a `nonblocking` function are dropped.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) enum Drops {
+    /// At the end of the body, in the real-time context.
+    #[default]
+    Inside,
+    /// After the real-time context is exited.
+    Outside,
+    /// Like `Outside`, and drops that remain in the real-time context are
+    /// reported.
+    Strict,
+}
+
+impl Drops {
+    pub(crate) fn parse(value: &LitStr) -> syn::Result<Self> {
+        match value.value().as_str() {
+            "inside" => Ok(Drops::Inside),
+            "outside" => Ok(Drops::Outside),
+            "strict" => Ok(Drops::Strict),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "expected `\"inside\"`, `\"outside\"` or `\"strict\"`",
+            )),
+        }
+    }
+
+    /// Moves the declarations of top-level locals that are dropped outside of
+    /// the real-time context out of `block`, turning their initializers into
+    /// assignments. The returned declarations go before the guard, so the
+    /// locals are dropped after it. Locals marked with
+    /// `#[rtsan(drop_outside)]` are moved in every mode.
+    ///
+    /// A local stays inside if it can not be declared up front without
+    /// changing its meaning: if its name is bound more than once, it borrows
+    /// a temporary, it has `else`, or its initializer mentions a local that
+    /// stays inside and may borrow from it. The type of a local is kept in
+    /// the body, where the items of the body are in scope.
+    ///
+    /// In strict mode, explicit `drop` calls and values replaced by an
+    /// assignment are reported at runtime, and every local that stays
+    /// inside, at any depth of the body, emits a warning.
+    pub(crate) fn hoist(
+        self,
+        krate: &Path,
+        sig: &Signature,
+        block: &mut Block,
+    ) -> syn::Result<(Vec<Local>, TokenStream)> {
+        // Names bound more than once can not be declared up front, the
+        // assignments would all go to the last declaration.
+        let mut bindings = HashMap::new();
+        for input in &sig.inputs {
+            if let FnArg::Typed(pat_type) = input {
+                count_bindings(&pat_type.pat, 2, &mut bindings);
+            }
+        }
+        for stmt in &block.stmts {
+            if let Stmt::Local(local) = stmt {
+                count_bindings(&local.pat, 1, &mut bindings);
+            }
+        }
+        let has_items = block.stmts.iter().any(|stmt| matches!(stmt, Stmt::Item(_)));
+
+        let mut warnings = TokenStream::new();
+        if self == Drops::Strict {
+            let mut strict = StrictDrops {
+                krate,
+                initialized: initialized(sig, block),
+                warnings: &mut warnings,
+                depth: 0,
+            };
+            strict.visit_block_mut(block);
+        }
+
+        let reasons = self.reasons(&block.stmts, &bindings, has_items);
+        let mut locals = Vec::new();
+        let mut stmts = Vec::new();
+        for (mut stmt, reason) in block.stmts.drain(..).zip(reasons) {
+            let Stmt::Local(local) = &mut stmt else {
+                stmts.push(stmt);
+                continue;
+            };
+            let marked = strip_attr(&mut local.attrs, "drop_outside");
+            if let Some(reason) = reason {
+                if marked {
+                    return Err(syn::Error::new_spanned(
+                        &*local,
+                        format!("this local can not be dropped outside, {reason}"),
+                    ));
+                }
+                if self == Drops::Strict && !local.attrs.iter().any(|a| is_rtsan_attr(a, "allow")) {
+                    warnings.extend(warning(local.pat.span(), "this local is dropped"));
+                }
+                stmts.push(stmt);
+                continue;
+            }
+
+            let attrs: Vec<_> = local
+                .attrs
+                .iter()
+                .filter(|attr| !attr.path().is_ident("rtsan"))
+                .cloned()
+                .collect();
+            let (pat, ty) = match &local.pat {
+                Pat::Type(pat_type) if local.init.is_some() => {
+                    ((*pat_type.pat).clone(), Some(&pat_type.ty))
+                }
+                pat => (pat.clone(), None),
+            };
+            let ident = binding(&pat).cloned();
+            if let Some(init) = &local.init {
+                let expr = &init.expr;
+                stmts.push(match ty {
+                    // the type names items of the body, so it stays in the body
+                    Some(ty) => syn::parse_quote! {
+                        #(#attrs)* #ident = ::core::convert::identity::<#ty>(#expr);
+                    },
+                    None => syn::parse_quote!(#(#attrs)* #ident = #expr;),
+                });
+            }
+            locals.push(Local {
+                attrs,
+                pat,
+                init: None,
+                ..local.clone()
+            });
+        }
+        block.stmts = stmts;
+        Ok((locals, warnings))
+    }
+
+    /// Why each statement that is a local has to stay inside, `None` for the
+    /// locals that can be declared before the guard and other statements.
+    /// A local that is used together with a local staying inside, in its
+    /// initializer or in a later statement, may borrow from it, and stays
+    /// inside too. This repeats until no more locals stay inside.
+    fn reasons(
+        self,
+        stmts: &[Stmt],
+        bindings: &HashMap<String, usize>,
+        has_items: bool,
+    ) -> Vec<Option<&'static str>> {
+        let mut reasons: Vec<_> = stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::Local(local) => {
+                    let marked = local
+                        .attrs
+                        .iter()
+                        .any(|attr| is_rtsan_attr(attr, "drop_outside"));
+                    if !marked && self == Drops::Inside {
+                        Some("")
+                    } else {
+                        stays_inside(local, bindings, has_items)
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+        loop {
+            let mut inside = HashSet::new();
+            for (stmt, reason) in stmts.iter().zip(&reasons) {
+                if let (Stmt::Local(local), Some(_)) = (stmt, reason) {
+                    collect_bindings(&local.pat, &mut inside);
+                }
+            }
+            let mut changed = false;
+            for (index, stmt) in stmts.iter().enumerate() {
+                let Stmt::Local(local) = stmt else {
+                    continue;
+                };
+                if reasons[index].is_some() {
+                    continue;
+                }
+                let own = collect_bindings_set(&local.pat);
+                if local
+                    .init
+                    .as_ref()
+                    .is_some_and(|init| mentions(&init.expr, &inside))
+                {
+                    reasons[index] =
+                        Some("it uses a local that is dropped inside and may borrow from it");
+                } else if stmts[index + 1..]
+                    .iter()
+                    .any(|stmt| stmt_mentions(stmt, &own) && stmt_mentions(stmt, &inside))
+                {
+                    reasons[index] = Some(
+                        "a later statement uses it together with a local that is dropped inside, \
+                         it may borrow from that local",
+                    );
+                }
+                changed |= reasons[index].is_some();
+            }
+            if !changed {
+                return reasons;
+            }
+        }
+    }
+}
+
+/// Why `local` has to stay inside, or `None` if it can be declared before
+/// the guard.
+fn stays_inside(
+    local: &Local,
+    bindings: &HashMap<String, usize>,
+    has_items: bool,
+) -> Option<&'static str> {
+    let Some(ident) = binding(&local.pat) else {
+        return Some("only a local bound to a single name can be");
+    };
+    if bindings[&ident.to_string()] != 1 {
+        return Some("its name is bound more than once in the function");
+    }
+    let Some(init) = &local.init else {
+        // without an initializer, the type goes with the declaration
+        return match (&local.pat, has_items) {
+            (Pat::Type(_), true) => {
+                Some("give it an initializer, its type may name items of the body")
+            }
+            _ => None,
+        };
+    };
+    if init.diverge.is_some() {
+        return Some("a local with `else` can not be");
+    }
+    if extends_temporary(&init.expr) {
+        return Some("it borrows a temporary that is dropped inside");
+    }
+    None
+}
+
+/// Reports the drops of a strict function that are visible in its body:
+/// `drop(value)` calls, the values replaced by assignments, and locals that
+/// stay inside at any depth.
+struct StrictDrops<'a> {
+    krate: &'a Path,
+    /// Names bound with a value, whose assignments replace a value.
+    initialized: HashSet<String>,
+    warnings: &'a mut TokenStream,
+    /// The block nesting, top-level locals are handled by `hoist`.
+    depth: usize,
+}
+
+impl VisitMut for StrictDrops<'_> {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        self.depth += 1;
+        visit_mut::visit_block_mut(self, block);
+        self.depth -= 1;
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        let allowed = stmt_attrs_mut(stmt)
+            .is_some_and(|attrs| attrs.iter().any(|attr| is_rtsan_attr(attr, "allow")));
+        if allowed {
+            return;
+        }
+        if let Stmt::Local(local) = stmt {
+            let trivial = local
+                .init
+                .as_ref()
+                .is_some_and(|init| matches!(*init.expr, Expr::Lit(_)));
+            if self.depth > 1 && !trivial {
+                self.warnings
+                    .extend(warning(local.pat.span(), "this local is dropped"));
+            }
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    // nested items are not executed as part of the function
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+        let Expr::Assign(assign) = expr else {
+            return;
+        };
+        let replaces = match &*assign.left {
+            Expr::Path(path) => path
+                .path
+                .get_ident()
+                .is_some_and(|ident| self.initialized.contains(&ident.to_string())),
+            Expr::Field(_) | Expr::Index(_) | Expr::Unary(_) => true,
+            _ => false,
+        };
+        if replaces {
+            let krate = self.krate;
+            let (attrs, left, right) = (&assign.attrs, &assign.left, &assign.right);
+            let span = assign.eq_token.span();
+            // the value is evaluated before the place, like in the assignment
+            *expr = syn::parse_quote_spanned!(span=>
+                #(#attrs)*
+                {
+                    let __value = #right;
+                    #krate::__private::replace_in_realtime(&mut #left, __value)
+                }
+            );
+        }
+    }
+
+    fn visit_expr_call_mut(&mut self, call: &mut ExprCall) {
+        visit_mut::visit_expr_call_mut(self, call);
+        let Expr::Path(path) = &*call.func else {
+            return;
+        };
+        let segments: Vec<_> = path
+            .path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect();
+        let is_drop = match segments.as_slice() {
+            [drop] => drop == "drop",
+            [module, drop] => module == "mem" && drop == "drop",
+            [krate, module, drop] => {
+                matches!(krate.as_str(), "std" | "core") && module == "mem" && drop == "drop"
+            }
+            _ => false,
+        };
+        if is_drop && call.args.len() == 1 {
+            let krate = self.krate;
+            let span = path.span();
+            *call.func = syn::parse_quote_spanned!(span=>
+                #krate::__private::drop_in_realtime
+            );
+        }
+    }
+}
+
+/// Removes `#[rtsan(name)]` from `attrs`, returns `true` if it was present.
+fn strip_attr(attrs: &mut Vec<Attribute>, name: &str) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !is_rtsan_attr(attr, name));
+    len != attrs.len()
+}
+
+/// The name bound by a pattern that is a single, optionally typed, name.
+fn binding(pat: &Pat) -> Option<&syn::Ident> {
+    match pat {
+        Pat::Ident(PatIdent {
+            by_ref: None,
+            subpat: None,
+            ident,
+            ..
+        }) => Some(ident),
+        Pat::Type(pat_type) => binding(&pat_type.pat),
+        _ => None,
+    }
+}
+
+/// The names bound by the parameters and by `let` statements with a value,
+/// without the names that are also bound by a `let` without a value, which
+/// may be assigned while uninitialized.
+fn initialized(sig: &Signature, block: &mut Block) -> HashSet<String> {
+    struct Lets {
+        initialized: HashSet<String>,
+        deferred: HashSet<String>,
+    }
+
+    impl VisitMut for Lets {
+        fn visit_local_mut(&mut self, local: &mut Local) {
+            let names = match local.init {
+                Some(_) => &mut self.initialized,
+                None => &mut self.deferred,
+            };
+            collect_bindings(&local.pat, names);
+            visit_mut::visit_local_mut(self, local);
+        }
+
+        fn visit_item_mut(&mut self, _item: &mut Item) {}
+    }
+
+    let mut lets = Lets {
+        initialized: HashSet::new(),
+        deferred: HashSet::new(),
+    };
+    for input in &sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            collect_bindings(&pat_type.pat, &mut lets.initialized);
+        }
+    }
+    lets.visit_block_mut(block);
+    lets.initialized
+        .retain(|name| !lets.deferred.contains(name));
+    lets.initialized
+}
+
+fn collect_bindings(pat: &Pat, names: &mut HashSet<String>) {
+    let mut counts = HashMap::new();
+    count_bindings(pat, 1, &mut counts);
+    names.extend(counts.into_keys());
+}
+
+fn collect_bindings_set(pat: &Pat) -> HashSet<String> {
+    let mut names = HashSet::new();
+    collect_bindings(pat, &mut names);
+    names
+}
+
+fn count_bindings(pat: &Pat, weight: usize, bindings: &mut HashMap<String, usize>) {
+    struct Count<'a>(usize, &'a mut HashMap<String, usize>);
+
+    impl VisitMut for Count<'_> {
+        fn visit_pat_ident_mut(&mut self, pat: &mut PatIdent) {
+            *self.1.entry(pat.ident.to_string()).or_default() += self.0;
+            visit_mut::visit_pat_ident_mut(self, pat);
+        }
+    }
+
+    Count(weight, bindings).visit_pat_mut(&mut pat.clone());
+}
+
+/// Finds the names of a set in expressions, including in the tokens of
+/// macro calls.
+struct Mentions<'a>(&'a HashSet<String>, bool);
+
+impl VisitMut for Mentions<'_> {
+    fn visit_expr_path_mut(&mut self, path: &mut ExprPath) {
+        if let Some(ident) = path.path.get_ident() {
+            self.1 |= self.0.contains(&ident.to_string());
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        self.1 |= tokens_mention(mac.tokens.clone(), self.0);
+    }
+
+    // nested items are not executed as part of the function
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+}
+
+/// Returns `true` if `expr` mentions one of `names`.
+fn mentions(expr: &Expr, names: &HashSet<String>) -> bool {
+    let mut visitor = Mentions(names, false);
+    visitor.visit_expr_mut(&mut expr.clone());
+    visitor.1
+}
+
+/// Returns `true` if `stmt` mentions one of `names`.
+fn stmt_mentions(stmt: &Stmt, names: &HashSet<String>) -> bool {
+    let mut visitor = Mentions(names, false);
+    visitor.visit_stmt_mut(&mut stmt.clone());
+    visitor.1
+}
+
+fn tokens_mention(tokens: TokenStream, names: &HashSet<String>) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => names.contains(&ident.to_string()),
+        TokenTree::Group(group) => tokens_mention(group.stream(), names),
+        _ => false,
+    })
+}
+
+/// Returns `true` if the initializer borrows a temporary, whose lifetime is
+/// only extended by a `let` statement and not by an assignment.
+fn extends_temporary(expr: &Expr) -> bool {
+    match expr {
+        Expr::Reference(_) => true,
+        Expr::Array(array) => array.elems.iter().any(extends_temporary),
+        Expr::Tuple(tuple) => tuple.elems.iter().any(extends_temporary),
+        Expr::Struct(expr) => expr
+            .fields
+            .iter()
+            .any(|field| extends_temporary(&field.expr)),
+        Expr::Paren(paren) => extends_temporary(&paren.expr),
+        Expr::Group(group) => extends_temporary(&group.expr),
+        Expr::Cast(cast) => extends_temporary(&cast.expr),
+        Expr::Block(block) => match block.block.stmts.last() {
+            Some(Stmt::Expr(expr, None)) => extends_temporary(expr),
+            _ => false,
+        },
+        _ => false,
+    }
+}
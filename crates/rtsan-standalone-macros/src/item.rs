@@ -0,0 +1,151 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    spanned::Spanned, Attribute, Block, ImplItem, Item, ItemImpl, ItemMod, Meta, Path, Signature,
+    TraitItemFn, Type,
+};
+
+use crate::traits::expand_trait;
+
+/// Names of the attribute macros of this crate. A function carrying one of
+/// them is expanded by its own attribute and left alone by a surrounding
+/// `impl` block or module attribute.
+const SANITIZE_ATTRIBUTES: [&str; 3] = ["nonblocking", "blocking", "no_sanitize_realtime"];
+
+/// Where a function is defined.
+pub(crate) struct Scope<'a> {
+    /// The `Self` type, if the function is defined in an `impl` block.
+    pub self_ty: Option<&'a Type>,
+}
+
+/// A rewrite of a single function body, shared by functions, `impl` blocks
+/// and inline modules.
+pub(crate) trait Transform {
+    fn transform(&self, scope: &Scope, sig: &Signature, block: Block) -> syn::Result<Block>;
+
+    /// The path of the `rtsan-standalone` crate in generated code.
+    fn crate_path(&self) -> syn::Result<Path>;
+}
+
+/// Applies `transform` to a function, to every method of an `impl` block or
+/// a trait, or to every function of an inline module.
+pub(crate) fn expand(mut item: Item, transform: &impl Transform) -> syn::Result<TokenStream> {
+    let mut extra = TokenStream::new();
+    match &mut item {
+        Item::Fn(function) => {
+            let scope = Scope { self_ty: None };
+            transform_block(transform, &scope, &function.sig, &mut function.block)?;
+        }
+        Item::Impl(item_impl) => expand_impl(item_impl, transform)?,
+        Item::Trait(item_trait) => extra = expand_trait(item_trait, transform)?,
+        Item::Mod(item_mod) if item_mod.content.is_some() => expand_mod(item_mod, transform)?,
+        Item::Verbatim(tokens) if syn::parse2::<TraitItemFn>(tokens.clone()).is_ok() => {
+            return Err(syn::Error::new(
+                item.span(),
+                "a method declaration can not be wrapped on its own, \
+                 apply the attribute to the trait as well to wrap its implementations",
+            ))
+        }
+        item => {
+            return Err(syn::Error::new(
+                item.span(),
+                "expected a function, an `impl` block, a trait or an inline module",
+            ))
+        }
+    }
+    let mut tokens = item.into_token_stream();
+    tokens.extend(extra);
+    Ok(tokens)
+}
+
+fn expand_impl(item_impl: &mut ItemImpl, transform: &impl Transform) -> syn::Result<()> {
+    let scope = Scope {
+        self_ty: Some(&item_impl.self_ty),
+    };
+    for impl_item in &mut item_impl.items {
+        if let ImplItem::Fn(method) = impl_item {
+            if !skip(&mut method.attrs) && method.sig.constness.is_none() {
+                transform_block(transform, &scope, &method.sig, &mut method.block)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn expand_mod(item_mod: &mut ItemMod, transform: &impl Transform) -> syn::Result<()> {
+    let Some((_, items)) = &mut item_mod.content else {
+        return Ok(());
+    };
+    let mut generated = Vec::new();
+    for item in items.iter_mut() {
+        let attrs = match item {
+            Item::Fn(function) => &mut function.attrs,
+            Item::Impl(item_impl) => &mut item_impl.attrs,
+            Item::Trait(item_trait) => &mut item_trait.attrs,
+            Item::Mod(nested) => &mut nested.attrs,
+            _ => continue,
+        };
+        if skip(attrs) {
+            continue;
+        }
+        match item {
+            Item::Fn(function) if function.sig.constness.is_none() => {
+                let scope = Scope { self_ty: None };
+                transform_block(transform, &scope, &function.sig, &mut function.block)?;
+            }
+            Item::Impl(item_impl) => expand_impl(item_impl, transform)?,
+            Item::Trait(item_trait) => generated.push(expand_trait(item_trait, transform)?),
+            Item::Mod(nested) => expand_mod(nested, transform)?,
+            _ => {}
+        }
+    }
+    // `Sanitized` implementations of traits in the module
+    items.extend(generated.into_iter().map(Item::Verbatim));
+    Ok(())
+}
+
+pub(crate) fn transform_block(
+    transform: &(impl Transform + ?Sized),
+    scope: &Scope,
+    sig: &Signature,
+    block: &mut Block,
+) -> syn::Result<()> {
+    let original = core::mem::replace(block, syn::parse_quote!({}));
+    *block = transform.transform(scope, sig, original)?;
+    Ok(())
+}
+
+/// Returns `true` if the item opted out with `#[rtsan(skip)]`, which gets
+/// removed, or if it carries its own sanitize attribute.
+pub(crate) fn skip(attrs: &mut Vec<Attribute>) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !is_rtsan_attr(attr, "skip"));
+    len != attrs.len() || attrs.iter().any(is_sanitize_attr)
+}
+
+pub(crate) fn is_sanitize_attr(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| SANITIZE_ATTRIBUTES.iter().any(|name| segment.ident == name))
+}
+
+/// Matches the inert helper attribute `#[rtsan(name)]`.
+pub(crate) fn is_rtsan_attr(attr: &Attribute, name: &str) -> bool {
+    let Meta::List(list) = &attr.meta else {
+        return false;
+    };
+    list.path.is_ident("rtsan")
+        && list
+            .parse_args::<syn::Ident>()
+            .is_ok_and(|ident| ident == name)
+}
+
+/// Returns `true` if `tokens` contain the `Self` type.
+pub(crate) fn contains_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "Self",
+        TokenTree::Group(group) => contains_self(group.stream()),
+        _ => false,
+    })
+}
@@ -2,9 +2,19 @@ extern crate proc_macro;
 extern crate quote;
 extern crate syn;
 
+mod blocking;
+mod crate_path;
+mod drops;
+mod item;
+mod lint;
+mod no_sanitize_realtime;
+mod nonblocking;
+mod realtime;
+mod test;
+mod traits;
+
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{parse_macro_input, Item};
 
 /// Enter real-time context in your function.
 /// When in a real-time context, RTSan interceptors will error if realtime
@@ -19,61 +29,114 @@ use syn::{parse_macro_input, ItemFn};
 ///     let _ = vec![0.0; 256]; // oops
 /// }
 /// ```
+///
+/// Also applies to `async fn`, `extern "C"` functions, `impl` blocks, inline
+/// modules and traits. On a trait, only default method bodies and calls
+/// through the `Sanitized` wrapper are sanitized, a call through `dyn Trait`
+/// or `T: Trait` to an implementation that is neither wrapped nor annotated
+/// is not.
+///
+/// # Arguments
+///
+/// - `drops = "inside" | "outside" | "strict"`: where the locals of the body
+///   are dropped, and whether drops inside are reported.
+/// - `warmup = N`: skips the real-time context for the first `N` calls.
+/// - `sample = 1/N`: sanitizes about one in `N` calls.
+/// - `domain = "name"`: makes the function part of a named `Domain`.
+/// - `fallback = expr`: the value an `extern "C"` function returns after a
+///   panic.
+/// - `lint = true`: warns about constructs that almost always allocate or
+///   block, silenced with `#[rtsan(allow)]` on a statement.
+/// - `crate = path`: the path of `rtsan_standalone` in the generated code.
+///
+/// See the documentation of the `rtsan_standalone` crate for details.
 #[proc_macro_attribute]
-pub fn nonblocking(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as ItemFn);
-    let attrs = input.attrs;
-    let vis = input.vis;
-    let sig = input.sig;
-    let block = input.block;
+pub fn nonblocking(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = nonblocking::Nonblocking::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
 
-    let output = quote! {
-        #(#attrs)*
-        #vis #sig {
-            let __guard = rtsan_standalone::ScopedSanitizeRealtime::default();
-            #block
-        }
-    };
-    TokenStream::from(output)
+    let input = parse_macro_input!(item as Item);
+    item::expand(input, &args)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 /// Allows the user to specify a function as not-real-time-safe.
+/// Can also be applied to an `impl` block, a trait or an inline module, see
+/// [`nonblocking`].
+///
+/// The function is reported with its fully qualified path and its generic
+/// parameters, for example `my_crate::Voice<f32>::load`. The path includes
+/// the `Self` type for every function of an annotated `impl` block, and for
+/// a function annotated on its own if it has a `self` receiver or mentions
+/// `Self` in its signature. Otherwise the attribute can not tell a function
+/// in an `impl` block from a free function, so an associated function like
+/// `fn load(path: &str) -> Voice` is reported as `my_crate::load`. Annotate
+/// the `impl` block, or use `name`, to tell such functions apart.
+///
+/// # Arguments
+///
+/// - `name = "..."` reports a custom name instead of the path.
+/// - `reason = "..."` appends the reason why the function must not be called
+///   from a real-time context to the report.
+/// - `safe` makes the wrappers of an `extern` block safe functions, see
+///   below.
 ///
 /// # Example
 ///
 /// ```ignore
 /// #[blocking]
 /// fn my_blocking_function() {}
+///
+/// #[blocking(name = "Sample::load", reason = "disk I/O")]
+/// fn load_sample(path: &str) {}
+/// ```
+///
+/// On an `extern` block, every foreign function gets a wrapper with the same
+/// name and visibility that reports the symbol name before forwarding the
+/// call. The wrappers stay `unsafe` like the foreign functions. Variadic
+/// functions can not be wrapped and need `#[rtsan(skip)]`.
+///
+/// ```ignore
+/// #[blocking(reason = "file I/O")]
+/// extern "C" {
+///     fn decoder_open(path: *const c_char) -> *mut Decoder;
+///     #[rtsan(skip)]
+///     fn decoder_log(format: *const c_char, ...);
+/// }
+/// ```
+///
+/// With `safe`, the wrappers are safe functions. The attribute then vouches
+/// that every function of the block can be called with any arguments of its
+/// signature, which the compiler can not check.
+///
+/// ```ignore
+/// #[blocking(safe, reason = "locks the device")]
+/// extern "C" {
+///     fn device_sync(id: u32) -> i32;
+/// }
+///
+/// let status = device_sync(0);
 /// ```
 #[proc_macro_attribute]
-pub fn blocking(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    // Parse the input token stream as a function
-    let input = parse_macro_input!(item as ItemFn);
-
-    // Extract components of the function
-    let attrs = input.attrs; // Attributes, including doc comments
-    let vis = input.vis; // Visibility modifier
-    let sig = input.sig; // Function signature (includes name, generics, and return type)
-    let block = input.block; // Function body
-    let mut function_name = sig.ident.to_string();
-    function_name.push('\0');
-    let function_name_bytes = function_name.into_bytes();
-    let function_name = core::ffi::CStr::from_bytes_with_nul(&function_name_bytes).unwrap();
+pub fn blocking(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = blocking::Blocking::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
 
-    // Generate the transformed function
-    let output = quote! {
-        #(#attrs)*
-        #vis #sig {
-            rtsan_standalone::notify_blocking_call(#function_name);
-            // Directly execute and return the block
-            #block
-        }
-    };
-
-    TokenStream::from(output)
+    let input = parse_macro_input!(item as Item);
+    match input {
+        Item::ForeignMod(foreign_mod) => blocking::expand_foreign(foreign_mod, &args),
+        input => item::expand(input, &args),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
 }
 
 /// Disable all RTSan error reporting in an otherwise real-time context.
+/// Can also be applied to an `impl` block, a trait or an inline module, see
+/// [`nonblocking`].
 ///
 /// # Example
 ///
@@ -84,23 +147,79 @@ pub fn blocking(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn no_sanitize_realtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    // Parse the input token stream as a function
-    let input = parse_macro_input!(item as ItemFn);
+pub fn no_sanitize_realtime(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = no_sanitize_realtime::NoSanitizeRealtime::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
 
-    // Extract the function signature and body
-    let attrs = input.attrs; // Attributes, including doc comments
-    let vis = input.vis; // Visibility modifier
-    let sig = input.sig; // Function signature (includes name, generics, and return type)
-    let block = input.block; // Function body
+    let input = parse_macro_input!(item as Item);
+    item::expand(input, &args)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Enter real-time context for a block of code, evaluating to the value of
+/// the block.
+/// Given a closure, returns a closure that enters the real-time context every
+/// time it is called, for example to sanitize callbacks handed to other APIs.
+///
+/// # Example
+///
+/// ```ignore
+/// fn process(data: &mut [f32]) {
+///     let buffer = vec![0.0; data.len()]; // ok
+///
+///     let sum: f32 = realtime! {
+///         data.iter().sum() // sanitized
+///     };
+///
+///     let mut callback = realtime!(move |output: &mut [f32]| {
+///         output.copy_from_slice(&buffer); // sanitized on every call
+///     });
+///     callback(data);
+/// }
+/// ```
+///
+/// `crate = path;` and `lint = true;` before the body work like the
+/// arguments of [`nonblocking`].
+#[proc_macro]
+pub fn realtime(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as realtime::Realtime)
+        .expand()
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// A test that checks whether its body is flagged by RTSan, see
+/// `testing::catch_violation`. The body runs in a real-time context in a
+/// forked child process. With `expect_violation = "function"` the test
+/// passes only if a violation in `function` is reported, with `expect_clean`
+/// it fails with the report of any violation. The runtime is initialized
+/// before the body runs.
+///
+/// The test is ignored if the build is not sanitized with `RTSAN_ENABLE`, or
+/// RTSan does not support the target. It needs the `testing` feature.
+///
+/// # Example
+///
+/// ```ignore
+/// #[rtsan_standalone::testing::test(expect_violation = "malloc")]
+/// fn process_allocates() {
+///     process(&mut [0.0; 256]);
+/// }
+///
+/// #[rtsan_standalone::testing::test(expect_clean)]
+/// fn process_with_capacity_is_clean() {
+///     process_into(&mut Vec::with_capacity(256));
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = test::Test::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
 
-    // Generate the transformed function
-    let output = quote! {
-        #(#attrs)*
-        #vis #sig {
-            let __guard = rtsan_standalone::ScopedDisabler::default();
-            #block
-        }
-    };
-    TokenStream::from(output)
+    args.expand(item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
@@ -0,0 +1,209 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{
+    visit_mut::{self, VisitMut},
+    Attribute, Block, Expr, ExprCall, ExprMethodCall, ExprRange, Item, Macro, Stmt,
+};
+
+use crate::item::is_rtsan_attr;
+
+/// Macros that allocate or block.
+const MACROS: [(&str, &str); 6] = [
+    ("vec", "`vec!` allocates memory"),
+    ("format", "`format!` allocates memory"),
+    ("print", "`print!` locks and writes to stdout"),
+    ("println", "`println!` locks and writes to stdout"),
+    ("eprint", "`eprint!` locks and writes to stderr"),
+    ("eprintln", "`eprintln!` locks and writes to stderr"),
+];
+
+/// Function paths, matched by their last two segments, that allocate or block.
+const FUNCTIONS: [(&str, &str, &str); 7] = [
+    ("Box", "new", "`Box::new` allocates memory"),
+    ("String", "from", "`String::from` allocates memory"),
+    (
+        "String",
+        "with_capacity",
+        "`String::with_capacity` allocates memory",
+    ),
+    (
+        "Vec",
+        "with_capacity",
+        "`Vec::with_capacity` allocates memory",
+    ),
+    ("Arc", "new", "`Arc::new` allocates memory"),
+    ("Rc", "new", "`Rc::new` allocates memory"),
+    ("thread", "sleep", "`thread::sleep` blocks the thread"),
+];
+
+/// Methods that allocate or block.
+const METHODS: [(&str, &str); 5] = [
+    ("to_string", "`.to_string()` allocates memory"),
+    ("to_owned", "`.to_owned()` allocates memory"),
+    ("to_vec", "`.to_vec()` allocates memory"),
+    ("collect", "`.collect()` usually allocates memory"),
+    ("lock", "`.lock()` can block on a contended mutex"),
+];
+
+/// Walks a function body and collects constructs that almost always allocate
+/// or block. Statements marked with `#[rtsan(allow)]` are skipped and the
+/// attribute is removed.
+#[derive(Default)]
+pub(crate) struct Lint {
+    warnings: Vec<(Span, &'static str)>,
+}
+
+impl Lint {
+    pub(crate) fn check(block: &mut Block) -> TokenStream {
+        let mut lint = Lint::default();
+        lint.visit_block_mut(block);
+        lint.into_warnings()
+    }
+
+    /// Like `check`, but drops the findings, without `lint = true`. The
+    /// `#[rtsan(allow)]` attributes are still removed.
+    pub(crate) fn allow(block: &mut Block) -> TokenStream {
+        Lint::default().visit_block_mut(block);
+        TokenStream::new()
+    }
+
+    /// Emits a warning for every finding. Stable Rust has no API for proc
+    /// macro warnings, so each one uses a deprecated item at the span of the
+    /// finding, and rustc reports it as the `deprecated` lint.
+    fn into_warnings(self) -> TokenStream {
+        self.warnings
+            .into_iter()
+            .map(|(span, what)| warning(span, what))
+            .collect()
+    }
+}
+
+/// A warning at `span` that `what` happens in a real-time context.
+pub(crate) fn warning(span: Span, what: &str) -> TokenStream {
+    let note = format!(
+        "not a deprecation, {what} in a real-time context. \
+         Silence it with `#[rtsan(allow)]` on the statement"
+    );
+    deprecation(span, "realtime_unsafe", &note)
+}
+
+/// A block using a deprecated unit struct called `name` at `span`, which
+/// warns with `note`.
+pub(crate) fn deprecation(span: Span, name: &str, note: &str) -> TokenStream {
+    let ident = Ident::new(name, Span::call_site());
+    let usage = Ident::new(name, span);
+    quote! {
+        {
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            struct #ident;
+            let _ = #usage;
+        }
+    }
+}
+
+impl VisitMut for Lint {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        let allowed = stmt_attrs_mut(stmt).is_some_and(|attrs| {
+            let len = attrs.len();
+            attrs.retain(|attr| !is_rtsan_attr(attr, "allow"));
+            len != attrs.len()
+        });
+        if !allowed {
+            visit_mut::visit_stmt_mut(self, stmt);
+        }
+    }
+
+    // nested items are not executed as part of the function
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        if let Some(segment) = mac.path.segments.last() {
+            if let Some((_, what)) = MACROS.iter().find(|(name, _)| segment.ident == name) {
+                self.warnings.push((segment.ident.span(), what));
+            }
+        }
+    }
+
+    fn visit_expr_call_mut(&mut self, call: &mut ExprCall) {
+        if let Expr::Path(path) = &*call.func {
+            let mut segments = path.path.segments.iter().rev();
+            if let (Some(function), Some(parent)) = (segments.next(), segments.next()) {
+                if let Some((_, _, what)) = FUNCTIONS
+                    .iter()
+                    .find(|(ty, name, _)| parent.ident == ty && function.ident == name)
+                {
+                    self.warnings.push((function.ident.span(), what));
+                }
+            }
+        }
+        visit_mut::visit_expr_call_mut(self, call);
+    }
+
+    fn visit_expr_method_call_mut(&mut self, call: &mut ExprMethodCall) {
+        if let Some((_, what)) = METHODS.iter().find(|(name, _)| call.method == name) {
+            self.warnings.push((call.method.span(), what));
+        }
+        visit_mut::visit_expr_method_call_mut(self, call);
+    }
+}
+
+pub(crate) fn stmt_attrs_mut(stmt: &mut Stmt) -> Option<&mut Vec<Attribute>> {
+    match stmt {
+        Stmt::Local(local) => Some(&mut local.attrs),
+        Stmt::Macro(mac) => Some(&mut mac.attrs),
+        Stmt::Expr(expr, _) => expr_attrs_mut(expr),
+        Stmt::Item(_) => None,
+    }
+}
+
+/// The attributes of `expr`. syn puts the attributes of a statement like
+/// `a = b` or `a + b` on its left operand. `Expr::Verbatim` has none, and
+/// variants added by later versions of syn are not known here.
+fn expr_attrs_mut(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    match expr {
+        Expr::Assign(expr) => expr_attrs_mut(&mut expr.left),
+        Expr::Binary(expr) => expr_attrs_mut(&mut expr.left),
+        Expr::Cast(expr) => expr_attrs_mut(&mut expr.expr),
+        Expr::Range(ExprRange {
+            start: Some(start), ..
+        }) => expr_attrs_mut(start),
+        Expr::Array(expr) => Some(&mut expr.attrs),
+        Expr::Async(expr) => Some(&mut expr.attrs),
+        Expr::Await(expr) => Some(&mut expr.attrs),
+        Expr::Block(expr) => Some(&mut expr.attrs),
+        Expr::Break(expr) => Some(&mut expr.attrs),
+        Expr::Call(expr) => Some(&mut expr.attrs),
+        Expr::Closure(expr) => Some(&mut expr.attrs),
+        Expr::Const(expr) => Some(&mut expr.attrs),
+        Expr::Continue(expr) => Some(&mut expr.attrs),
+        Expr::Field(expr) => Some(&mut expr.attrs),
+        Expr::ForLoop(expr) => Some(&mut expr.attrs),
+        Expr::Group(expr) => Some(&mut expr.attrs),
+        Expr::If(expr) => Some(&mut expr.attrs),
+        Expr::Index(expr) => Some(&mut expr.attrs),
+        Expr::Infer(expr) => Some(&mut expr.attrs),
+        Expr::Let(expr) => Some(&mut expr.attrs),
+        Expr::Lit(expr) => Some(&mut expr.attrs),
+        Expr::Loop(expr) => Some(&mut expr.attrs),
+        Expr::Macro(expr) => Some(&mut expr.attrs),
+        Expr::Match(expr) => Some(&mut expr.attrs),
+        Expr::MethodCall(expr) => Some(&mut expr.attrs),
+        Expr::Paren(expr) => Some(&mut expr.attrs),
+        Expr::Path(expr) => Some(&mut expr.attrs),
+        Expr::Range(expr) => Some(&mut expr.attrs),
+        Expr::RawAddr(expr) => Some(&mut expr.attrs),
+        Expr::Reference(expr) => Some(&mut expr.attrs),
+        Expr::Repeat(expr) => Some(&mut expr.attrs),
+        Expr::Return(expr) => Some(&mut expr.attrs),
+        Expr::Struct(expr) => Some(&mut expr.attrs),
+        Expr::Try(expr) => Some(&mut expr.attrs),
+        Expr::TryBlock(expr) => Some(&mut expr.attrs),
+        Expr::Tuple(expr) => Some(&mut expr.attrs),
+        Expr::Unary(expr) => Some(&mut expr.attrs),
+        Expr::Unsafe(expr) => Some(&mut expr.attrs),
+        Expr::While(expr) => Some(&mut expr.attrs),
+        Expr::Yield(expr) => Some(&mut expr.attrs),
+        _ => None,
+    }
+}
@@ -0,0 +1,34 @@
+use syn::{meta::ParseNestedMeta, Block, Path, Signature};
+
+use crate::crate_path::CratePath;
+use crate::item::{Scope, Transform};
+
+/// Arguments of the `no_sanitize_realtime` attribute.
+#[derive(Default)]
+pub(crate) struct NoSanitizeRealtime {
+    krate: CratePath,
+}
+
+impl NoSanitizeRealtime {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("crate") {
+            self.krate.parse(&meta)
+        } else {
+            Err(meta.error("unsupported no_sanitize_realtime argument, expected `crate`"))
+        }
+    }
+}
+
+impl Transform for NoSanitizeRealtime {
+    fn transform(&self, _scope: &Scope, _sig: &Signature, block: Block) -> syn::Result<Block> {
+        let krate = self.crate_path()?;
+        Ok(syn::parse_quote!({
+            let __guard = #krate::ScopedDisabler::default();
+            #block
+        }))
+    }
+
+    fn crate_path(&self) -> syn::Result<Path> {
+        self.krate.get()
+    }
+}
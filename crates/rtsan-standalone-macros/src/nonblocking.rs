@@ -0,0 +1,253 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, BinOp, Block, Expr, ExprBinary, ExprLit, Lit, LitBool, LitStr, Path,
+    Signature, Type,
+};
+
+use crate::crate_path::CratePath;
+use crate::drops::Drops;
+use crate::item::{Scope, Transform};
+use crate::lint::Lint;
+
+/// Arguments of the `nonblocking` attribute.
+#[derive(Default)]
+pub(crate) struct Nonblocking {
+    fallback: Option<Expr>,
+    drops: Drops,
+    warmup: Option<Expr>,
+    sample: Option<Expr>,
+    domain: Option<LitStr>,
+    /// Set by `lint = true`, which warns about constructs in the body that
+    /// almost always allocate or block.
+    lint: bool,
+    krate: CratePath,
+}
+
+impl Nonblocking {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("fallback") {
+            self.fallback = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("drops") {
+            self.drops = Drops::parse(&meta.value()?.parse()?)?;
+            Ok(())
+        } else if meta.path.is_ident("warmup") {
+            self.warmup = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("sample") {
+            self.sample = Some(parse_rate(meta.value()?.parse()?)?);
+            Ok(())
+        } else if meta.path.is_ident("domain") {
+            self.domain = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("lint") {
+            self.lint = meta.value()?.parse::<LitBool>()?.value;
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            self.krate.parse(&meta)
+        } else {
+            Err(meta.error(
+                "unsupported nonblocking argument, \
+                 expected `fallback`, `drops`, `warmup`, `sample`, `domain`, `lint` or `crate`",
+            ))
+        }
+    }
+
+    /// Enters the real-time context until the end of the scope. With a
+    /// warmup, the first calls of the function are not sanitized, counted by
+    /// a `static` in the function body. With sampling, only some calls are.
+    /// A domain brings its own configuration, including the sample rate.
+    /// The scope is named after the function, see `current_scope`.
+    fn guard(&self, krate: &Path, name: &TokenStream) -> syn::Result<TokenStream> {
+        let guard = match (&self.sample, &self.domain) {
+            (Some(rate), None) => quote! {
+                #krate::ScopedSanitizeRealtime::sampled(#rate)
+                    .map(|__guard| __guard.with_scope(#name))
+            },
+            (None, Some(domain)) => {
+                let domain = domain_cell(krate, domain);
+                quote!(#krate::ScopedSanitizeRealtime::in_domain(#domain).with_scope(#name))
+            }
+            (None, None) => quote!(#krate::ScopedSanitizeRealtime::default().with_scope(#name)),
+            (Some(rate), Some(_)) => {
+                return Err(syn::Error::new_spanned(
+                    rate,
+                    "`sample` can not be combined with `domain`, \
+                     set the sample rate of the domain with `Domain::set_sample_rate`",
+                ))
+            }
+        };
+        match &self.warmup {
+            Some(warmup) => {
+                let guard = match &self.sample {
+                    Some(_) => guard,
+                    None => quote!(::core::option::Option::Some(#guard)),
+                };
+                Ok(quote! {
+                    static __RTSAN_WARMUP: #krate::__private::Warmup =
+                        #krate::__private::Warmup::new();
+                    let __guard = if __RTSAN_WARMUP.done(#warmup) {
+                        #guard
+                    } else {
+                        ::core::option::Option::None
+                    };
+                })
+            }
+            None => Ok(quote!(let __guard = #guard;)),
+        }
+    }
+}
+
+impl Transform for Nonblocking {
+    fn transform(&self, scope: &Scope, sig: &Signature, mut block: Block) -> syn::Result<Block> {
+        let krate = self.crate_path()?;
+        let name = scope_name(scope, sig);
+        let (locals, drop_warnings) = self.drops.hoist(&krate, sig, &mut block)?;
+        let mut warnings = if self.lint {
+            Lint::check(&mut block)
+        } else {
+            Lint::allow(&mut block)
+        };
+        warnings.extend(drop_warnings);
+
+        if self.fallback.is_some() && (sig.asyncness.is_some() || !is_c_abi(sig)) {
+            return Err(syn::Error::new(
+                sig.ident.span(),
+                "`fallback` is only supported on `extern \"C\"` functions",
+            ));
+        }
+
+        if sig.asyncness.is_some() {
+            if self.drops != Drops::Inside || !locals.is_empty() {
+                return Err(syn::Error::new(
+                    sig.ident.span(),
+                    "locals of an `async fn` are dropped while the future is polled, \
+                     they can not be dropped outside",
+                ));
+            }
+            if self.warmup.is_some() || self.sample.is_some() {
+                return Err(syn::Error::new(
+                    sig.ident.span(),
+                    "`warmup` and `sample` are not supported on `async fn`",
+                ));
+            }
+            // The guard must not live inside the future, otherwise it stays alive
+            // across `.await` points while the executor runs other tasks.
+            let future = match &self.domain {
+                Some(domain) => {
+                    let domain = domain_cell(&krate, domain);
+                    quote!(#krate::RealtimeFuture::in_domain(async move #block, #domain))
+                }
+                None => quote!(#krate::RealtimeFuture::new(async move #block)),
+            };
+            Ok(syn::parse_quote!({
+                #warnings
+                #future.with_scope(#name).await
+            }))
+        } else if is_c_abi(sig) {
+            let guard = self.guard(&krate, &name)?;
+            // A panic must not unwind into the caller, see `__private::ffi_call`.
+            // Without a `fallback` the process is aborted, the return type
+            // may have no value to give.
+            let fallback = match &self.fallback {
+                Some(fallback) => quote!(#fallback),
+                None => quote!(#krate::__private::ffi_abort()),
+            };
+            Ok(syn::parse_quote!({
+                #warnings
+                #krate::__private::ffi_call(
+                    move || {
+                        #(#locals)*
+                        #guard
+                        #[allow(clippy::redundant_locals)]
+                        #block
+                    },
+                    || #fallback,
+                )
+            }))
+        } else {
+            let guard = self.guard(&krate, &name)?;
+            // Parameters are dropped after the guard, outside of the real-time
+            // context. A rebinding like `let buffer = buffer;` moves one into
+            // the body and changes where it is dropped, it is not redundant.
+            Ok(syn::parse_quote!({
+                #warnings
+                #(#locals)*
+                #guard
+                #[allow(clippy::redundant_locals)]
+                #block
+            }))
+        }
+    }
+
+    fn crate_path(&self) -> syn::Result<Path> {
+        self.krate.get()
+    }
+}
+
+/// The name of the function as a `&'static str`, `module::Type::function`
+/// for methods. Generic arguments are left out, they are not known as a
+/// constant.
+fn scope_name(scope: &Scope, sig: &Signature) -> TokenStream {
+    let ty = scope.self_ty.and_then(|ty| match ty {
+        Type::Path(ty) if !ty.path.is_ident("Self") => ty.path.segments.last(),
+        _ => None,
+    });
+    let name = match ty {
+        Some(ty) => format!("::{}::{}", ty.ident, sig.ident),
+        None => format!("::{}", sig.ident),
+    };
+    quote!(::core::concat!(::core::module_path!(), #name))
+}
+
+/// The `Domain` called `name`, looked up once per function in a `static`.
+fn domain_cell(krate: &Path, name: &LitStr) -> TokenStream {
+    quote!({
+        static __RTSAN_DOMAIN: #krate::__private::DomainCell =
+            #krate::__private::DomainCell::new(#name);
+        __RTSAN_DOMAIN.get()
+    })
+}
+
+/// The `N` of a `1/N` sampling rate.
+fn parse_rate(rate: Expr) -> syn::Result<Expr> {
+    if let Expr::Binary(ExprBinary {
+        left,
+        op: BinOp::Div(_),
+        right,
+        ..
+    }) = &rate
+    {
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Int(one), ..
+        }) = &**left
+        {
+            if one.base10_digits() == "1" {
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Int(rate),
+                    ..
+                }) = &**right
+                {
+                    if rate.base10_parse::<u32>()? == 0 {
+                        return Err(syn::Error::new_spanned(
+                            right,
+                            "`1/0` is not a sample rate, use `1/1` to sanitize every call",
+                        ));
+                    }
+                }
+                return Ok((**right).clone());
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(rate, "expected a rate `1/N`"))
+}
+
+/// Returns `true` for functions callable from C, which must not unwind.
+fn is_c_abi(sig: &Signature) -> bool {
+    sig.abi.as_ref().is_some_and(|abi| {
+        abi.name
+            .as_ref()
+            .map_or(true, |name| matches!(name.value().as_str(), "C" | "system"))
+    })
+}
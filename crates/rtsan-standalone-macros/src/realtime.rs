@@ -0,0 +1,95 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Block, Expr, ExprClosure, Ident, LitBool, Path, Stmt, Token,
+};
+
+use crate::crate_path::CratePath;
+use crate::lint::Lint;
+
+/// Input of the `realtime!` macro: an optional `crate = path;` and
+/// `lint = true;`, followed by either a closure or a list of statements.
+pub(crate) struct Realtime {
+    krate: CratePath,
+    lint: bool,
+    body: Body,
+}
+
+enum Body {
+    Closure(ExprClosure),
+    Block(Vec<Stmt>),
+}
+
+impl Parse for Realtime {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut krate = CratePath::default();
+        let mut lint = false;
+        loop {
+            if input.peek(Token![crate]) && input.peek2(Token![=]) {
+                input.parse::<Token![crate]>()?;
+                input.parse::<Token![=]>()?;
+                krate.set(input.parse()?);
+            } else if input.peek(Ident)
+                && input.peek2(Token![=])
+                && input.fork().parse::<Ident>()? == "lint"
+            {
+                input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                lint = input.parse::<LitBool>()?.value;
+            } else {
+                break;
+            }
+            input.parse::<Token![;]>()?;
+        }
+        let fork = input.fork();
+        let body = if fork.parse::<ExprClosure>().is_ok() && fork.is_empty() {
+            Body::Closure(input.parse()?)
+        } else {
+            Body::Block(Block::parse_within(input)?)
+        };
+        Ok(Realtime { krate, lint, body })
+    }
+}
+
+impl Realtime {
+    pub(crate) fn expand(self) -> syn::Result<TokenStream> {
+        let krate = self.krate.get()?;
+        Ok(match self.body {
+            Body::Closure(mut closure) => {
+                // Splice the statements of a block body, nesting the block
+                // would trigger `unused_braces` on the user's code.
+                let stmts = match *closure.body {
+                    Expr::Block(block) if block.attrs.is_empty() && block.label.is_none() => {
+                        block.block.stmts
+                    }
+                    body => vec![Stmt::Expr(body, None)],
+                };
+                let mut block = syn::parse_quote!({ #(#stmts)* });
+                let stmts = guarded(&krate, self.lint, &mut block);
+                closure.body = syn::parse_quote!({ #stmts });
+                quote!(#closure)
+            }
+            Body::Block(stmts) => {
+                let mut block = syn::parse_quote!({ #(#stmts)* });
+                let stmts = guarded(&krate, self.lint, &mut block);
+                quote!({ #stmts })
+            }
+        })
+    }
+}
+
+/// The statements of `block`, preceded by the lint warnings and the guard.
+fn guarded(krate: &Path, lint: bool, block: &mut Block) -> TokenStream {
+    let warnings = if lint {
+        Lint::check(block)
+    } else {
+        Lint::allow(block)
+    };
+    let stmts = &block.stmts;
+    quote! {
+        #warnings
+        let __guard = #krate::ScopedSanitizeRealtime::default();
+        #(#stmts)*
+    }
+}
@@ -0,0 +1,83 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{meta::ParseNestedMeta, spanned::Spanned, ItemFn, LitStr, ReturnType};
+
+use crate::crate_path::CratePath;
+
+/// Arguments of the `test` attribute.
+#[derive(Default)]
+pub(crate) struct Test {
+    expect: Option<Expect>,
+    krate: CratePath,
+}
+
+enum Expect {
+    Violation(LitStr),
+    Clean,
+}
+
+impl Test {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        let expect = if meta.path.is_ident("expect_violation") {
+            Expect::Violation(meta.value()?.parse()?)
+        } else if meta.path.is_ident("expect_clean") {
+            Expect::Clean
+        } else if meta.path.is_ident("crate") {
+            return self.krate.parse(&meta);
+        } else {
+            return Err(meta.error(
+                "unsupported test argument, expected `expect_violation`, `expect_clean` or `crate`",
+            ));
+        };
+        if self.expect.is_some() {
+            return Err(meta.error("`expect_violation` and `expect_clean` can not be combined"));
+        }
+        self.expect = Some(expect);
+        Ok(())
+    }
+
+    /// Runs the body in a forked child, see `testing::catch_violation`.
+    pub(crate) fn expand(&self, item: TokenStream) -> syn::Result<TokenStream> {
+        let Some(expect) = &self.expect else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected `expect_violation = \"...\"` or `expect_clean`",
+            ));
+        };
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = syn::parse2(item)?;
+        if let Some(asyncness) = sig.asyncness {
+            return Err(syn::Error::new(
+                asyncness.span(),
+                "`expect_violation` and `expect_clean` can not be used on an `async fn`",
+            ));
+        }
+        if !sig.inputs.is_empty() || !matches!(sig.output, ReturnType::Default) {
+            return Err(syn::Error::new(
+                sig.span(),
+                "`expect_violation` and `expect_clean` need a test without arguments that returns `()`",
+            ));
+        }
+
+        let krate = self.krate.get()?;
+        let expect = match expect {
+            Expect::Violation(function) => {
+                quote!(#krate::__private::Expect::Violation(#function))
+            }
+            Expect::Clean => quote!(#krate::__private::Expect::Clean),
+        };
+        Ok(quote! {
+            #krate::__test! {
+                #(#attrs)*
+                #vis #sig {
+                    #krate::ensure_initialized();
+                    #krate::__private::run_test(#expect, || #block);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,246 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    meta::ParseNestedMeta, spanned::Spanned, Attribute, FnArg, GenericParam, ItemTrait, Meta,
+    Signature, TraitItem, TraitItemFn, Type, TypeParamBound,
+};
+
+use crate::blocking::Blocking;
+use crate::item::{
+    contains_self, is_rtsan_attr, is_sanitize_attr, skip, transform_block, Scope, Transform,
+};
+use crate::no_sanitize_realtime::NoSanitizeRealtime;
+use crate::nonblocking::Nonblocking;
+
+/// Supertraits that `rtsan_standalone::Sanitized` implements automatically.
+const AUTO_TRAITS: [&str; 4] = ["Send", "Sync", "Unpin", "UnwindSafe"];
+
+/// Applies `transform` to the default bodies of a trait.
+///
+/// Implementors can override those and never see the attribute, so the trait
+/// is also implemented for `rtsan_standalone::Sanitized<P>`, forwarding every
+/// call to `P` through `transform`. Boxing `Sanitized(processor)` as a trait
+/// object sanitizes every call, whatever the implementation of `P` looks like.
+///
+/// Calls through `dyn Trait` or `T: Trait` to an implementation that is not
+/// wrapped are not sanitized, unless the implementation is annotated itself.
+/// The methods of a trait object already take part in method resolution like
+/// inherent methods, so an `impl dyn Trait` with the same names would make
+/// every such call ambiguous instead of wrapping it.
+///
+/// A method with its own attribute, like `#[blocking]`, is rewritten with
+/// that attribute instead, which also works for methods without a body.
+/// If the trait can not be forwarded, an error names the item in the way,
+/// unless the trait opts out of the wrapper with `#[rtsan(defaults_only)]`.
+pub(crate) fn expand_trait(
+    item_trait: &mut ItemTrait,
+    transform: &impl Transform,
+) -> syn::Result<TokenStream> {
+    let self_ty: Type = syn::parse_quote!(Self);
+    let scope = Scope {
+        self_ty: Some(&self_ty),
+    };
+    let len = item_trait.attrs.len();
+    item_trait
+        .attrs
+        .retain(|attr| !is_rtsan_attr(attr, "defaults_only"));
+    let defaults_only = len != item_trait.attrs.len();
+    if !defaults_only {
+        if let Some((span, reason)) = unforwardable(item_trait) {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "`Sanitized<P>` can not implement `{}`, {reason}. Annotate the \
+                     implementations instead, and put `#[rtsan(defaults_only)]` on the trait \
+                     to sanitize only its default method bodies",
+                    item_trait.ident
+                ),
+            ));
+        }
+    }
+    let ident = &item_trait.ident;
+    let (_, ty_generics, _) = item_trait.generics.split_for_impl();
+    let trait_path = quote!(#ident #ty_generics);
+    let krate = transform.crate_path()?;
+
+    let mut forwards = Vec::new();
+    for trait_item in &mut item_trait.items {
+        match trait_item {
+            TraitItem::Fn(method) => {
+                let own = own_transform(&mut method.attrs)?;
+                let skipped = skip(&mut method.attrs) || method.sig.constness.is_some();
+                let transform: Option<&dyn Transform> = match &own {
+                    _ if skipped => None,
+                    Some(own) => Some(own.as_ref()),
+                    None => Some(transform),
+                };
+                if let (Some(transform), Some(block)) = (transform, &mut method.default) {
+                    transform_block(transform, &scope, &method.sig, block)?;
+                }
+                forwards.push(forward_fn(&trait_path, method, transform, &scope)?);
+            }
+            TraitItem::Type(ty) => {
+                let ident = &ty.ident;
+                let (impl_generics, ty_generics, where_clause) = ty.generics.split_for_impl();
+                forwards.push(quote! {
+                    type #ident #impl_generics = <__P as #trait_path>::#ident #ty_generics
+                        #where_clause;
+                });
+            }
+            TraitItem::Const(constant) => {
+                let ident = &constant.ident;
+                let ty = &constant.ty;
+                forwards.push(quote!(const #ident: #ty = <__P as #trait_path>::#ident;));
+            }
+            _ => {}
+        }
+    }
+    if defaults_only {
+        return Ok(TokenStream::new());
+    }
+
+    let mut generics = item_trait.generics.clone();
+    generics.params.push(syn::parse_quote!(__P: #trait_path));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #trait_path for #krate::Sanitized<__P> #where_clause {
+            #(#forwards)*
+        }
+    })
+}
+
+/// Removes the sanitize attribute of a trait method and parses it.
+fn own_transform(attrs: &mut Vec<Attribute>) -> syn::Result<Option<Box<dyn Transform>>> {
+    let Some(index) = attrs.iter().position(is_sanitize_attr) else {
+        return Ok(None);
+    };
+    let attr = attrs.remove(index);
+    let parse_args = |parser: &mut dyn FnMut(ParseNestedMeta) -> syn::Result<()>| match &attr.meta {
+        Meta::Path(_) => Ok(()),
+        _ => attr.parse_nested_meta(parser),
+    };
+    let name = attr.path().segments.last().map(|segment| &segment.ident);
+    let transform: Box<dyn Transform> = match name {
+        Some(name) if name == "nonblocking" => {
+            let mut args = Nonblocking::default();
+            parse_args(&mut |meta| args.parse(meta))?;
+            Box::new(args)
+        }
+        Some(name) if name == "blocking" => {
+            let mut args = Blocking::default();
+            parse_args(&mut |meta| args.parse(meta))?;
+            Box::new(args)
+        }
+        _ => {
+            let mut args = NoSanitizeRealtime::default();
+            parse_args(&mut |meta| args.parse(meta))?;
+            Box::new(args)
+        }
+    };
+    Ok(Some(transform))
+}
+
+/// A method of the `Sanitized` implementation that forwards to the wrapped
+/// implementation.
+fn forward_fn(
+    trait_path: &TokenStream,
+    method: &TraitItemFn,
+    transform: Option<&dyn Transform>,
+    scope: &Scope,
+) -> syn::Result<TokenStream> {
+    let mut sig = method.sig.clone();
+    let mut args = Vec::new();
+    let mut impl_trait_args = false;
+    for (i, input) in sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(receiver) => match (&receiver.reference, &receiver.mutability) {
+                (Some(_), Some(_)) => args.push(quote!(&mut self.0)),
+                (Some(_), None) => args.push(quote!(&self.0)),
+                (None, _) => {
+                    receiver.mutability = None;
+                    args.push(quote!(self.0));
+                }
+            },
+            FnArg::Typed(pat_type) => {
+                impl_trait_args |= matches!(*pat_type.ty, Type::ImplTrait(_));
+                let arg = format_ident!("__arg{i}");
+                *pat_type.pat = syn::parse_quote!(#arg);
+                args.push(arg.into_token_stream());
+            }
+        }
+    }
+
+    let ident = &sig.ident;
+    let generic_args: Vec<_> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = if generic_args.is_empty() || impl_trait_args {
+        quote!()
+    } else {
+        quote!(::<#(#generic_args),*>)
+    };
+    let mut call = quote!(<__P as #trait_path>::#ident #turbofish(#(#args),*));
+    if sig.asyncness.is_some() {
+        call = quote!(#call.await);
+    }
+    if sig.unsafety.is_some() {
+        call = quote!(unsafe { #call });
+    }
+
+    let block = syn::parse_quote!({ #call });
+    let block = match transform {
+        Some(transform) => transform.transform(scope, &sig, block)?,
+        None => block,
+    };
+    let cfgs = method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"));
+    Ok(quote!(#(#cfgs)* #[inline] #sig #block))
+}
+
+/// The first item that keeps the trait from being implemented for
+/// `Sanitized<P>` by forwarding every item to `P`, and why.
+fn unforwardable(item_trait: &ItemTrait) -> Option<(Span, &'static str)> {
+    for bound in &item_trait.supertraits {
+        let forwardable = match bound {
+            TypeParamBound::Trait(bound) => bound
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| AUTO_TRAITS.iter().any(|name| segment.ident == name)),
+            TypeParamBound::Lifetime(_) => true,
+            _ => false,
+        };
+        if !forwardable {
+            return Some((
+                bound.span(),
+                "supertraits other than auto traits are not forwarded",
+            ));
+        }
+    }
+    item_trait.items.iter().find_map(|item| match item {
+        TraitItem::Fn(method) if !is_forwardable_fn(&method.sig) => Some((
+            method.sig.span(),
+            "`Self` can only appear as the receiver of a method",
+        )),
+        TraitItem::Fn(_) | TraitItem::Const(_) | TraitItem::Type(_) => None,
+        item => Some((item.span(), "this item can not be forwarded")),
+    })
+}
+
+/// `Self` is `Sanitized<P>` in the forwarding implementation, so it can only
+/// appear as a plain receiver.
+fn is_forwardable_fn(sig: &Signature) -> bool {
+    sig.inputs.iter().all(|input| match input {
+        FnArg::Receiver(receiver) => receiver.colon_token.is_none(),
+        FnArg::Typed(pat_type) => !contains_self(pat_type.ty.to_token_stream()),
+    }) && !contains_self(sig.output.to_token_stream())
+}
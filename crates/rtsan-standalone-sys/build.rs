@@ -74,6 +74,10 @@ fn main() {
         return;
     }
 
+    // Pre-built and locally built libraries are both built from this release
+    let llvm_version = LLVM_BRANCH_NAME.trim_start_matches("llvmorg-");
+    println!("cargo:rustc-env=RTSAN_RUNTIME_VERSION={llvm_version}");
+
     // Check if pre-built libraries should be downloaded
     if cfg!(feature = "prebuilt-libs") {
         check_tool("curl");
@@ -191,6 +195,7 @@ fn setup_linking(lib_path: &Path, target_os: &str) {
             .trim_start_matches("lib")
             .trim_end_matches(".a");
         println!("cargo:rustc-link-lib=static={lib_stem}");
+        println!("cargo:rustc-env=RTSAN_LINK_MODE=static");
     } else {
         // Adjust install_name for macOS
         run_command(
@@ -212,6 +217,7 @@ fn setup_linking(lib_path: &Path, target_os: &str) {
                 .trim_start_matches("lib")
                 .trim_end_matches(".dylib")
         );
+        println!("cargo:rustc-env=RTSAN_LINK_MODE=dynamic");
 
         // Set rpath to OUT_DIR
         println!("cargo:rustc-link-arg=-Wl,-rpath,{}", out_dir.display());
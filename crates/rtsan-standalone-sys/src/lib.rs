@@ -1,5 +1,14 @@
 #![no_std]
 
+/// LLVM version of the linked runtime, `None` for a library given with
+/// `RTSAN_LIB_PATH`.
+#[cfg(rtsan_enabled)]
+pub const RUNTIME_VERSION: Option<&str> = option_env!("RTSAN_RUNTIME_VERSION");
+
+/// How the runtime is linked, `"static"` or `"dynamic"`.
+#[cfg(rtsan_enabled)]
+pub const LINK_MODE: &str = env!("RTSAN_LINK_MODE");
+
 #[cfg(rtsan_enabled)]
 extern "C" {
     pub fn __rtsan_realtime_enter();
@@ -8,4 +17,6 @@ extern "C" {
     pub fn __rtsan_enable();
     pub fn __rtsan_ensure_initialized();
     pub fn __rtsan_notify_blocking_call(blocking_function_name: *const core::ffi::c_char);
+    pub fn __sanitizer_set_death_callback(callback: Option<extern "C" fn()>);
+    pub fn __sanitizer_set_report_fd(fd: *mut core::ffi::c_void);
 }
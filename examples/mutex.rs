@@ -11,6 +11,8 @@ pub struct State {
 fn process(state: Arc<Mutex<State>>) {
     // Attempt to acquire the lock on the shared state.
     // This lock triggers a syscall because another thread is holding the lock at the same time.
+    // `#[rtsan(allow)]` silences the compile-time warning to see the report at runtime.
+    #[rtsan(allow)]
     let mut guard = state.lock().unwrap();
     // Safely increment the shared state's value while holding the lock.
     guard.value += 1;
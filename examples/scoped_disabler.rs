@@ -10,15 +10,15 @@ fn my_function() {
     };
 
     // Panics and assertions trigger the allocator before printing the actual error.
-    // Wrapping them in a `scoped_disabler` ensures the correct reason for the panic is shown.
-    scoped_disabler! {
-        assert_eq!(my_data.len(), 256);
-    };
+    // With the panic hook installed in `main`, a failing assertion is reported as
+    // a panic in real-time context with its message.
+    assert_eq!(my_data.len(), 256);
 
     let my_data2 = not_sanitized_function();
 
     // The implicit drop at the end of a scope triggers the sanitizer.
-    // Explicitly dropping the allocated data prevents this.
+    // Explicitly dropping the allocated data prevents this, as does
+    // `#[nonblocking(drops = "outside")]`.
     scoped_disabler! {
         drop(my_data);
         drop(my_data2);
@@ -36,6 +36,7 @@ fn main() {
     // Always call this function at the start of your program
     // to initialize the real-time sanitizer.
     ensure_initialized();
+    install_panic_hook();
 
     my_function(); // Execute the sanitized function
 }
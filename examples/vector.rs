@@ -3,6 +3,8 @@ use rtsan_standalone::*;
 // add the nonblocking macro to activate the sanitizer for this function
 #[nonblocking]
 fn my_function() {
+    // silence the compile-time warning to see the report at runtime
+    #[rtsan(allow)]
     let _ = vec![0.0; 256]; // oops
 }
 
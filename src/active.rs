@@ -0,0 +1,90 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static ACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// Environment variable read by [`ensure_initialized`](crate::ensure_initialized).
+#[cfg(feature = "std")]
+const ACTIVE_VAR: &str = "RTSAN_ACTIVE";
+
+/// Switches sanitizing on or off at runtime, for a binary built with RTSan
+/// enabled. It is on by default. While it is off, [`realtime_enter`] and
+/// [`ScopedSanitizeRealtime`] do not enter the real-time context, so no
+/// violations are reported.
+///
+/// The switch can also be set with the `RTSAN_ACTIVE` environment variable,
+/// `RTSAN_ACTIVE=0` starts with sanitizing off, and with signals, see
+/// [`install_signal_switch`].
+///
+/// Real-time scopes that already entered stay sanitized until they end.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// #[nonblocking]
+/// fn process() {
+///     let _ = vec![0.0; 256]; // not reported while switched off
+/// }
+///
+/// set_active(false);
+/// process();
+/// set_active(true);
+/// ```
+///
+/// [`realtime_enter`]: crate::realtime_enter
+/// [`ScopedSanitizeRealtime`]: crate::ScopedSanitizeRealtime
+/// [`install_signal_switch`]: crate::install_signal_switch
+#[inline]
+pub fn set_active(active: bool) {
+    ACTIVE.store(active, Ordering::Relaxed);
+}
+
+/// Returns `true` if sanitizing is switched on, see [`set_active`].
+#[inline]
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Applies `RTSAN_ACTIVE`, which accepts `0`, `false` and `off`, or `1`,
+/// `true` and `on`. Other values are ignored.
+#[cfg(feature = "std")]
+pub(crate) fn read_env() {
+    // reading the environment allocates, possibly in a real-time context
+    let _disabler = crate::ScopedDisabler::default();
+    match std::env::var(ACTIVE_VAR).as_deref() {
+        Ok("0" | "false" | "off") => set_active(false),
+        Ok("1" | "true" | "on") => set_active(true),
+        _ => {}
+    }
+}
+
+/// Installs signal handlers that switch sanitizing on with `SIGUSR1` and off
+/// with `SIGUSR2`, see [`set_active`]. Replaces previous handlers of both
+/// signals.
+///
+/// ```sh
+/// RTSAN_ACTIVE=0 ./staging-build &
+/// kill -USR1 $!  # start reporting violations
+/// kill -USR2 $!  # stop again
+/// ```
+#[cfg(unix)]
+pub fn install_signal_switch() {
+    extern "C" fn on_signal(signal: libc::c_int) {
+        // an atomic store is async-signal-safe
+        set_active(signal == libc::SIGUSR1);
+    }
+
+    // SAFETY: a zeroed `sigaction` is valid, the handler, flags and mask
+    // are set before it is installed.
+    unsafe {
+        let mut action: libc::sigaction = core::mem::zeroed();
+        action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        // interrupted system calls are restarted instead of failing with `EINTR`
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        for signal in [libc::SIGUSR1, libc::SIGUSR2] {
+            libc::sigaction(signal, &action, core::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! The calls behind [`realtime_enter`](crate::realtime_enter),
+//! [`realtime_exit`](crate::realtime_exit), [`disable`](crate::disable),
+//! [`enable`](crate::enable) and blocking call notifications.
+//!
+//! Without the `backend` feature they always go to the RTSan runtime. With
+//! it, a [`Backend`] set with `set_backend` receives them instead.
+
+use core::ffi::CStr;
+
+/// Receives the real-time events of all threads, see `set_backend`.
+pub trait Backend: Sync {
+    fn realtime_enter(&self);
+    fn realtime_exit(&self);
+    fn disable(&self);
+    fn enable(&self);
+    fn notify_blocking_call(&self, function_name: &CStr);
+}
+
+/// The RTSan runtime, which does nothing unless sanitizing is enabled with
+/// `RTSAN_ENABLE`. The default backend.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Runtime;
+
+impl Backend for Runtime {
+    #[inline]
+    fn realtime_enter(&self) {
+        #[cfg(rtsan_enabled)]
+        unsafe {
+            rtsan_standalone_sys::__rtsan_realtime_enter();
+        }
+    }
+
+    #[inline]
+    fn realtime_exit(&self) {
+        #[cfg(rtsan_enabled)]
+        unsafe {
+            rtsan_standalone_sys::__rtsan_realtime_exit();
+        }
+    }
+
+    #[inline]
+    fn disable(&self) {
+        #[cfg(rtsan_enabled)]
+        unsafe {
+            rtsan_standalone_sys::__rtsan_disable();
+        }
+    }
+
+    #[inline]
+    fn enable(&self) {
+        #[cfg(rtsan_enabled)]
+        unsafe {
+            rtsan_standalone_sys::__rtsan_enable();
+        }
+    }
+
+    #[inline]
+    #[allow(unused_variables)]
+    fn notify_blocking_call(&self, function_name: &CStr) {
+        #[cfg(rtsan_enabled)]
+        unsafe {
+            rtsan_standalone_sys::__rtsan_notify_blocking_call(function_name.as_ptr());
+        }
+        #[cfg(all(rtsan_enabled, feature = "std"))]
+        crate::domain::after_blocking_call();
+    }
+}
+
+#[cfg(not(feature = "backend"))]
+#[inline]
+pub(crate) fn get() -> Runtime {
+    Runtime
+}
+
+#[cfg(feature = "backend")]
+pub use registry::*;
+
+#[cfg(feature = "backend")]
+mod registry {
+    use core::ffi::CStr;
+    use core::ptr;
+    use core::sync::atomic::{AtomicPtr, Ordering};
+    use std::cell::RefCell;
+    use std::string::String;
+    use std::sync::{Mutex, PoisonError};
+    use std::vec::Vec;
+
+    use super::{Backend, Runtime};
+
+    /// The backend set with [`set_backend`], `null` for [`Runtime`].
+    static BACKEND: AtomicPtr<&'static dyn Backend> = AtomicPtr::new(ptr::null_mut());
+
+    /// Sends the real-time events of all threads to `backend`. Set it at
+    /// startup, before any real-time context is entered, otherwise the
+    /// contexts entered before are exited on the new backend. Setting a
+    /// backend again, for example to switch back to it, does not allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::*;
+    ///
+    /// #[nonblocking]
+    /// fn process() {}
+    ///
+    /// set_backend(&Recording);
+    /// process();
+    /// assert_eq!(Recording::take(), [Event::RealtimeEnter, Event::RealtimeExit]);
+    /// ```
+    pub fn set_backend(backend: &'static dyn Backend) {
+        /// Every backend set so far. The reference is a fat pointer, which
+        /// does not fit into an atomic, so the atomic points to a leaked box
+        /// holding it, and each backend is boxed only once.
+        static SET: Mutex<Vec<&'static &'static dyn Backend>> = Mutex::new(Vec::new());
+
+        let mut set = SET.lock().unwrap_or_else(PoisonError::into_inner);
+        let boxed = match set.iter().find(|boxed| ptr::eq(***boxed, backend)) {
+            Some(boxed) => *boxed,
+            None => {
+                let boxed = &*std::boxed::Box::leak(std::boxed::Box::new(backend));
+                set.push(boxed);
+                boxed
+            }
+        };
+        BACKEND.store(ptr::from_ref(boxed).cast_mut(), Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn get() -> &'static dyn Backend {
+        let backend = BACKEND.load(Ordering::Acquire);
+        if backend.is_null() {
+            &Runtime
+        } else {
+            // SAFETY: set from a leaked box in `set_backend`.
+            unsafe { *backend }
+        }
+    }
+
+    /// Ignores all events, for example to run a sanitized build without
+    /// reporting.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct NoOp;
+
+    impl Backend for NoOp {
+        fn realtime_enter(&self) {}
+        fn realtime_exit(&self) {}
+        fn disable(&self) {}
+        fn enable(&self) {}
+        fn notify_blocking_call(&self, _function_name: &CStr) {}
+    }
+
+    /// An event received by the [`Recording`] backend.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum Event {
+        RealtimeEnter,
+        RealtimeExit,
+        Disable,
+        Enable,
+        /// A blocking call with the name it was reported with.
+        BlockingCall(String),
+    }
+
+    std::thread_local! {
+        static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Records the events of each thread instead of reporting violations, so
+    /// tests can check how code enters and exits real-time contexts, on any
+    /// platform and without `RTSAN_ENABLE`.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Recording;
+
+    impl Recording {
+        /// Returns the events recorded on the current thread and clears them.
+        pub fn take() -> Vec<Event> {
+            EVENTS.with(|events| events.take())
+        }
+
+        fn record(event: Event) {
+            let _ = EVENTS.try_with(|events| events.borrow_mut().push(event));
+        }
+    }
+
+    impl Backend for Recording {
+        fn realtime_enter(&self) {
+            Self::record(Event::RealtimeEnter);
+        }
+
+        fn realtime_exit(&self) {
+            Self::record(Event::RealtimeExit);
+        }
+
+        fn disable(&self) {
+            Self::record(Event::Disable);
+        }
+
+        fn enable(&self) {
+            Self::record(Event::Enable);
+        }
+
+        fn notify_blocking_call(&self, function_name: &CStr) {
+            let name = function_name.to_string_lossy().into_owned();
+            Self::record(Event::BlockingCall(name));
+        }
+    }
+}
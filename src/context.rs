@@ -0,0 +1,188 @@
+//! Tracks the real-time context of each thread on the Rust side, so it can
+//! be inspected whether or not the RTSan runtime is linked.
+
+use std::cell::Cell;
+
+thread_local! {
+    /// Number of entered real-time contexts on this thread.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Number of `disable` calls not yet followed by `enable`.
+    static DISABLED: Cell<usize> = const { Cell::new(0) };
+    /// Name of the innermost named real-time scope.
+    static SCOPE: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+// Guards can be dropped while thread locals are destroyed, so the state is
+// accessed with `try_with` and ignored once it is gone.
+
+pub(crate) fn enter() {
+    let _ = DEPTH.try_with(|depth| depth.set(depth.get() + 1));
+}
+
+/// Leaves a real-time context, returning `false` in debug builds if the
+/// thread was not in one, so the call must not reach the runtime. Release
+/// builds pass it on, like builds without the `std` feature.
+#[track_caller]
+pub(crate) fn exit() -> bool {
+    match DEPTH.try_with(decrement) {
+        Ok(0) => {
+            unbalanced("realtime_exit", "realtime_enter");
+            return !cfg!(debug_assertions);
+        }
+        Ok(1) => crate::panic::realtime_left(),
+        _ => {}
+    }
+    true
+}
+
+pub(crate) fn disable() {
+    let _ = DISABLED.try_with(|disabled| disabled.set(disabled.get() + 1));
+}
+
+/// Enables reporting again, returning `false` in debug builds without a
+/// matching `disable`, so the call must not reach the runtime.
+#[track_caller]
+pub(crate) fn enable() -> bool {
+    if DISABLED.try_with(decrement) == Ok(0) {
+        unbalanced("enable", "disable");
+        return !cfg!(debug_assertions);
+    }
+    true
+}
+
+/// Decrements `counter`, returning its previous value.
+fn decrement(counter: &Cell<usize>) -> usize {
+    let previous = counter.get();
+    counter.set(previous.saturating_sub(1));
+    previous
+}
+
+/// Reports a call without a matching call before it, in debug builds.
+#[track_caller]
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+fn unbalanced(call: &str, pair: &str) {
+    #[cfg(debug_assertions)]
+    report(format_args!(
+        "`{call}` at {} without a matching `{pair}` on this thread",
+        core::panic::Location::caller()
+    ));
+}
+
+/// Prints a misuse of the guards or the paired functions.
+#[cfg(debug_assertions)]
+fn report(message: core::fmt::Arguments) {
+    // printing may allocate, possibly in a real-time context
+    let _disabler = crate::ScopedDisabler::default();
+    std::eprintln!("rtsan-standalone: {message}");
+}
+
+/// The thread that created a guard, checked when the guard is dropped in
+/// debug builds. Guards are `!Send`, so only unsafe code can move them.
+pub(crate) struct Owner {
+    #[cfg(debug_assertions)]
+    thread: usize,
+}
+
+impl Owner {
+    pub(crate) fn current() -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            thread: thread_id(),
+        }
+    }
+
+    /// Reports if the guard called `guard` is dropped on another thread.
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn check(&self, guard: &str) {
+        #[cfg(debug_assertions)]
+        if self.thread != thread_id() {
+            report(format_args!(
+                "`{guard}` dropped on another thread than the one that created it, \
+                 the real-time state of both threads is now unbalanced"
+            ));
+        }
+    }
+}
+
+/// Identifies the current thread by the address of one of its thread locals,
+/// `0` once they are destroyed.
+#[cfg(debug_assertions)]
+fn thread_id() -> usize {
+    DEPTH
+        .try_with(|depth| depth as *const Cell<usize> as usize)
+        .unwrap_or(0)
+}
+
+/// Makes `name` the current scope, returning the previous one.
+pub(crate) fn replace_scope(name: Option<&'static str>) -> Option<&'static str> {
+    SCOPE.try_with(|scope| scope.replace(name)).unwrap_or(None)
+}
+
+/// Returns `true` if the current thread is in a real-time context, entered
+/// with [`realtime_enter`](crate::realtime_enter), a [`ScopedSanitizeRealtime`]
+/// or one of the macros. Reporting may still be disabled, see [`is_disabled`].
+///
+/// The context is tracked by this crate, so the answer is the same whether
+/// or not sanitizing is enabled with `RTSAN_ENABLE`. Scopes skipped because
+/// sanitizing is switched off with [`set_active`](crate::set_active), or by
+/// sampling, do not count.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// fn mix(output: &mut Vec<f32>, len: usize) {
+///     if is_realtime() {
+///         output.truncate(len); // use the preallocated buffer
+///     } else {
+///         output.resize(len, 0.0);
+///     }
+/// }
+///
+/// #[nonblocking]
+/// fn process(output: &mut Vec<f32>) {
+///     assert!(is_realtime());
+///     mix(output, 16);
+/// }
+/// ```
+///
+/// [`ScopedSanitizeRealtime`]: crate::ScopedSanitizeRealtime
+pub fn is_realtime() -> bool {
+    realtime_depth() > 0
+}
+
+/// The number of nested real-time contexts of the current thread, `0`
+/// outside of a real-time context. See [`is_realtime`].
+pub fn realtime_depth() -> usize {
+    DEPTH.try_with(Cell::get).unwrap_or(0)
+}
+
+/// Returns `true` if error reporting is disabled on the current thread, by
+/// [`disable`](crate::disable), a [`ScopedDisabler`](crate::ScopedDisabler)
+/// or one of the macros.
+pub fn is_disabled() -> bool {
+    DISABLED.try_with(Cell::get).unwrap_or(0) > 0
+}
+
+/// The name of the innermost [`nonblocking`](crate::nonblocking) function
+/// that entered the real-time context on the current thread, like
+/// `"my_crate::Voice::process"`. Scopes without a name, like the
+/// [`realtime`](crate::realtime) macro, keep the name of the enclosing scope.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// #[nonblocking]
+/// fn process() {
+///     assert!(current_scope().unwrap().ends_with("::process"));
+/// }
+///
+/// process();
+/// assert_eq!(current_scope(), None);
+/// ```
+pub fn current_scope() -> Option<&'static str> {
+    SCOPE.try_with(Cell::get).unwrap_or(None)
+}
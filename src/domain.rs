@@ -0,0 +1,365 @@
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+
+/// Maximum number of domains in a process.
+pub const MAX_DOMAINS: usize = 16;
+/// Maximum number of allowlist entries per domain.
+pub const MAX_ALLOWED: usize = 16;
+
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const READY: u8 = 2;
+
+/// A string stored in atomics, set once.
+struct Name {
+    ptr: AtomicPtr<u8>,
+    len: AtomicUsize,
+}
+
+impl Name {
+    const fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Publishes `name`, readers see it once `ptr` is set.
+    fn set(&self, name: &'static str) {
+        self.len.store(name.len(), Ordering::Relaxed);
+        self.ptr.store(name.as_ptr().cast_mut(), Ordering::Release);
+    }
+
+    fn get(&self) -> Option<&'static str> {
+        let ptr = self.ptr.load(Ordering::Acquire);
+        if ptr.is_null() {
+            return None;
+        }
+        let len = self.len.load(Ordering::Relaxed);
+        // SAFETY: `ptr` and `len` were taken from a `&'static str` in `set`.
+        Some(unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr, len)) })
+    }
+}
+
+struct Slot {
+    state: AtomicU8,
+    name: Name,
+    enabled: AtomicBool,
+    sample_rate: AtomicU32,
+    allowed: [Name; MAX_ALLOWED],
+    /// Claimed entries of `allowed`.
+    allowed_len: AtomicUsize,
+}
+
+impl Slot {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NAME: Name = Name::new();
+
+    const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            name: Name::new(),
+            enabled: AtomicBool::new(true),
+            sample_rate: AtomicU32::new(1),
+            allowed: [Self::NAME; MAX_ALLOWED],
+            allowed_len: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const SLOT: Slot = Slot::new();
+
+/// The registry, a fixed number of slots so registering never allocates.
+static SLOTS: [Slot; MAX_DOMAINS] = [SLOT; MAX_DOMAINS];
+
+/// The name of the domain after the slots, see [`Domain::get`].
+const DEFAULT_NAME: &str = "default";
+static DEFAULT: Slot = Slot::new();
+
+/// A named kind of real-time thread, like audio or motor control, with its
+/// own configuration. Domains are registered on first use and can be
+/// configured at runtime from any thread.
+/// Used with [`ScopedSanitizeRealtime::for_domain`] or
+/// `#[nonblocking(domain = "...")]`.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// #[nonblocking(domain = "sensors")]
+/// fn fuse(data: &[f32]) -> f32 {
+///     data.iter().sum()
+/// }
+///
+/// let sensors = Domain::get("sensors");
+/// sensors.set_sample_rate(10);
+/// sensors.allow("my_app::log_reading");
+/// Domain::get("motor").set_enabled(false);
+/// ```
+///
+/// [`ScopedSanitizeRealtime::for_domain`]: crate::ScopedSanitizeRealtime::for_domain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domain {
+    index: usize,
+}
+
+impl Domain {
+    /// Returns the domain called `name`, registering it if needed. Looks the
+    /// name up in the registry on every call, the macros do it once per
+    /// function.
+    ///
+    /// Beyond [`MAX_DOMAINS`] domains, the scopes of further names fall back
+    /// to the domain called `"default"`, which is reported once on stderr
+    /// with the `std` feature.
+    pub fn get(name: &'static str) -> Domain {
+        if name == DEFAULT_NAME {
+            return Self::DEFAULT;
+        }
+        for (index, slot) in SLOTS.iter().enumerate() {
+            loop {
+                match slot.state.load(Ordering::Acquire) {
+                    READY if slot.name.get() == Some(name) => return Domain { index },
+                    READY => break,
+                    // another thread is registering, wait for its name
+                    WRITING => core::hint::spin_loop(),
+                    _ => {
+                        if slot
+                            .state
+                            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Acquire)
+                            .is_ok()
+                        {
+                            slot.name.set(name);
+                            slot.state.store(READY, Ordering::Release);
+                            return Domain { index };
+                        }
+                    }
+                }
+            }
+        }
+        overflowed(name);
+        Self::DEFAULT
+    }
+
+    const DEFAULT: Domain = Domain { index: MAX_DOMAINS };
+
+    fn slot(self) -> &'static Slot {
+        SLOTS.get(self.index).unwrap_or(&DEFAULT)
+    }
+
+    pub fn name(self) -> &'static str {
+        if self == Self::DEFAULT {
+            return DEFAULT_NAME;
+        }
+        self.slot().name.get().unwrap_or_default()
+    }
+
+    /// Enables or disables sanitizing of scopes in this domain, enabled by
+    /// default.
+    pub fn set_enabled(self, enabled: bool) {
+        self.slot().enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(self) -> bool {
+        self.slot().enabled.load(Ordering::Relaxed)
+    }
+
+    /// Sanitizes only about one in `rate` scopes of this domain, see
+    /// [`ScopedSanitizeRealtime::sampled`](crate::ScopedSanitizeRealtime::sampled).
+    /// `1`, the default, sanitizes every scope. The `RTSAN_SAMPLE_RATE`
+    /// environment variable does not override the rate of a domain.
+    pub fn set_sample_rate(self, rate: u32) {
+        self.slot().sample_rate.store(rate, Ordering::Relaxed);
+    }
+
+    pub fn sample_rate(self) -> u32 {
+        self.slot().sample_rate.load(Ordering::Relaxed)
+    }
+
+    /// Allows calls to blocking functions whose reported name starts with
+    /// `prefix` in this domain, for example `"my_crate::Sample::load"` or
+    /// `"my_crate::logging::"`. Covers functions marked with
+    /// [`blocking`](crate::blocking) and [`notify_blocking_call`](crate::notify_blocking_call),
+    /// violations of the intercepted library functions are suppressed with
+    /// RTSan suppressions. Needs the `std` feature, which tracks the domain of
+    /// each thread.
+    ///
+    /// Returns `false` if the allowlist already holds [`MAX_ALLOWED`] entries.
+    pub fn allow(self, prefix: &'static str) -> bool {
+        let slot = self.slot();
+        let index = slot.allowed_len.fetch_add(1, Ordering::Relaxed);
+        match slot.allowed.get(index) {
+            Some(entry) => {
+                entry.set(prefix);
+                true
+            }
+            None => {
+                slot.allowed_len.fetch_sub(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// Returns `true` if the allowlist has an entry that `name` starts with.
+    pub fn allows(self, name: &[u8]) -> bool {
+        self.slot()
+            .allowed
+            .iter()
+            .filter_map(Name::get)
+            .any(|prefix| name.starts_with(prefix.as_bytes()))
+    }
+
+    /// Returns `true` if a scope of this domain should be sanitized.
+    pub(crate) fn hit(self) -> bool {
+        let rate = self.sample_rate();
+        self.is_enabled() && (!cfg!(rtsan_enabled) || crate::sample::hit_exact(rate))
+    }
+}
+
+/// Reports the first name that did not fit into the registry.
+#[cfg(feature = "std")]
+fn overflowed(name: &str) {
+    /// Set once the registry overflowed and it was reported.
+    static OVERFLOWED: AtomicBool = AtomicBool::new(false);
+
+    if OVERFLOWED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    // printing may allocate, possibly in a real-time context
+    let _disabler = crate::ScopedDisabler::default();
+    std::eprintln!(
+        "rtsan-standalone: more than {MAX_DOMAINS} real-time domains, \
+         `{name}` and later domains use the domain `{DEFAULT_NAME}`"
+    );
+}
+
+#[cfg(not(feature = "std"))]
+fn overflowed(_name: &str) {}
+
+/// The domain of a function with `#[nonblocking(domain = "...")]`, looked
+/// up in the registry on its first call.
+pub struct DomainCell {
+    name: &'static str,
+    /// The index of the domain plus one, `0` until it is looked up.
+    index: AtomicUsize,
+}
+
+impl DomainCell {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            index: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Domain {
+        match self.index.load(Ordering::Relaxed) {
+            0 => {
+                let domain = Domain::get(self.name);
+                self.index.store(domain.index + 1, Ordering::Relaxed);
+                domain
+            }
+            index => Domain { index: index - 1 },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod current {
+    use core::cell::Cell;
+
+    use super::Domain;
+
+    std::thread_local! {
+        static CURRENT: Cell<Option<Domain>> = const { Cell::new(None) };
+    }
+
+    /// Makes a domain current on this thread until dropped.
+    pub(crate) struct Current {
+        previous: Option<Domain>,
+    }
+
+    impl Current {
+        pub(crate) fn enter(domain: Domain) -> Self {
+            Self {
+                previous: CURRENT.replace(Some(domain)),
+            }
+        }
+    }
+
+    impl Drop for Current {
+        fn drop(&mut self) {
+            CURRENT.set(self.previous);
+        }
+    }
+
+    /// The domain of the innermost real-time scope on this thread.
+    pub fn current() -> Option<Domain> {
+        CURRENT.get()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod current {
+    use super::Domain;
+
+    pub(crate) struct Current;
+
+    impl Current {
+        pub(crate) fn enter(_domain: Domain) -> Self {
+            Self
+        }
+    }
+
+    pub fn current() -> Option<Domain> {
+        None
+    }
+}
+
+pub(crate) use current::Current;
+
+impl Domain {
+    /// The domain of the innermost sanitized scope with a domain on this
+    /// thread. Always `None` without the `std` feature.
+    pub fn current() -> Option<Domain> {
+        current::current()
+    }
+}
+
+/// Returns `true` if the current domain allows the blocking call `name`.
+pub(crate) fn allows(name: &[u8]) -> bool {
+    Domain::current().is_some_and(|domain| domain.allows(name))
+}
+
+/// Appends the current domain to the report that stops the process, called
+/// by the death callback.
+#[cfg(all(rtsan_enabled, feature = "std"))]
+pub(crate) fn on_death() {
+    write_domain();
+}
+
+/// Appends the current domain to the report of a blocking call, if the
+/// runtime reported it and returned, with `halt_on_error=false`. Reports of
+/// intercepted calls are not seen by this crate while the thread is still
+/// in the call, so they are only named when they stop the process.
+#[cfg(all(rtsan_enabled, feature = "std"))]
+pub(crate) fn after_blocking_call() {
+    if crate::context::is_realtime() && !crate::context::is_disabled() {
+        write_domain();
+    }
+}
+
+/// Writes the line naming the current domain, if any, without allocating.
+#[cfg(all(rtsan_enabled, feature = "std"))]
+pub(crate) fn write_domain() {
+    let Some(domain) = Domain::current() else {
+        return;
+    };
+    let (prefix, suffix) = crate::report::DOMAIN_LINE;
+    crate::report::write(prefix.as_bytes());
+    crate::report::write(domain.name().as_bytes());
+    crate::report::write(suffix.as_bytes());
+    crate::report::write(b"\n");
+}
@@ -0,0 +1,37 @@
+//! Initializes the runtime before `main`, so binaries linking this crate do
+//! not have to call [`ensure_initialized`](crate::ensure_initialized).
+//!
+//! The constructor runs before `main`, the threads of the test harness and
+//! the constructors of crates linked after this one. It is a fallback,
+//! `ensure_initialized` at the start of `main` is the guaranteed path.
+
+/// Registered in the constructor section of the binary, `.init_array` on ELF
+/// platforms and `__mod_init_func` on Apple platforms.
+#[used]
+#[cfg_attr(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ),
+    link_section = ".init_array"
+)]
+#[cfg_attr(
+    any(target_os = "macos", target_os = "ios"),
+    link_section = "__DATA,__mod_init_func"
+)]
+static CONSTRUCTOR: extern "C" fn() = constructor;
+
+extern "C" fn constructor() {
+    crate::ensure_initialized();
+}
+
+/// Refers to the constructor from every entry of a real-time context. The
+/// linker may leave out a `#[used]` static of an rlib if nothing else in its
+/// object file is referenced, rust-lang/rust#47384.
+#[inline]
+pub(crate) fn keep() {
+    core::hint::black_box(&CONSTRUCTOR);
+}
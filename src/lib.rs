@@ -1,7 +1,49 @@
 #![doc = include_str!("../README.md")]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
-pub use rtsan_standalone_macros::*;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+#[cfg(not(feature = "backend"))]
+use backend::Backend as _;
+
+#[cfg(unix)]
+pub use active::install_signal_switch;
+pub use active::{is_active, set_active};
+#[cfg(feature = "backend")]
+pub use backend::{set_backend, Backend, Event, NoOp, Recording, Runtime};
+#[cfg(feature = "std")]
+pub use context::{current_scope, is_disabled, is_realtime, realtime_depth};
+pub use domain::{Domain, MAX_ALLOWED, MAX_DOMAINS};
+#[cfg(feature = "std")]
+pub use panic::install_panic_hook;
+#[cfg(feature = "std")]
+pub use report::ViolationKind;
+pub use rtsan_standalone_macros::{blocking, no_sanitize_realtime, nonblocking, realtime};
+pub use status::{require_enabled, status, LinkMode, Status};
+#[cfg(all(feature = "std", unix))]
+pub use violation::{set_violation_handler, Violation};
+
+mod active;
+mod backend;
+#[cfg(feature = "std")]
+mod context;
+mod domain;
+#[cfg(rtsan_enabled)]
+mod init;
+#[cfg(feature = "std")]
+mod panic;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg_attr(not(rtsan_enabled), allow(dead_code))]
+mod sample;
+mod status;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(all(feature = "std", unix))]
+mod violation;
 
 /// Enter real-time context.
 /// When in a real-time context, RTSan interceptors will error if realtime
@@ -26,11 +68,15 @@ pub use rtsan_standalone_macros::*;
 ///     let _ = vec![0.0; 256]; // oops!
 /// }
 /// ```
+///
+/// Does nothing while sanitizing is switched off with [`set_active`]. With the
+/// `std` feature, [`realtime_exit`] still leaves a context entered before the
+/// switch, without it switching in between leaves the calls unpaired. Prefer
+/// [`ScopedSanitizeRealtime`], which remembers whether it entered.
 #[inline]
 pub fn realtime_enter() {
-    #[cfg(rtsan_enabled)]
-    unsafe {
-        rtsan_standalone_sys::__rtsan_realtime_enter();
+    if is_active() {
+        sys_realtime_enter();
     }
 }
 
@@ -56,12 +102,43 @@ pub fn realtime_enter() {
 ///     let _ = vec![0.0; 256]; // oops!
 /// }
 /// ```
+///
+/// Does nothing while sanitizing is switched off with [`set_active`], unless
+/// the thread is in a real-time context entered before the switch. With the
+/// `std` feature, a call without a matching [`realtime_enter`] on the same
+/// thread is reported on stderr and ignored in debug builds. Release builds
+/// pass it on to the runtime.
 #[inline]
+#[track_caller]
 pub fn realtime_exit() {
+    // the depth of the thread tells whether the matching enter reached the
+    // runtime, whatever the switch says now
+    #[cfg(feature = "std")]
+    let entered = is_realtime() || is_active();
+    #[cfg(not(feature = "std"))]
+    let entered = is_active();
+    if entered {
+        sys_realtime_exit();
+    }
+}
+
+#[inline]
+fn sys_realtime_enter() {
     #[cfg(rtsan_enabled)]
-    unsafe {
-        rtsan_standalone_sys::__rtsan_realtime_exit();
+    init::keep();
+    #[cfg(feature = "std")]
+    context::enter();
+    backend::get().realtime_enter();
+}
+
+#[inline]
+#[track_caller]
+fn sys_realtime_exit() {
+    #[cfg(feature = "std")]
+    if !context::exit() {
+        return;
     }
+    backend::get().realtime_exit();
 }
 
 /// Disable all RTSan error reporting in an otherwise real-time context.
@@ -90,12 +167,12 @@ pub fn realtime_exit() {
 ///         let mut data = vec![0.0; 16]; // ok
 ///     });
 /// }
+/// ```
 #[inline]
 pub fn disable() {
-    #[cfg(rtsan_enabled)]
-    unsafe {
-        rtsan_standalone_sys::__rtsan_disable();
-    }
+    #[cfg(feature = "std")]
+    context::disable();
+    backend::get().disable();
 }
 
 /// Re-enable all RTSan error reporting.
@@ -124,18 +201,29 @@ pub fn disable() {
 ///         let mut data = vec![0.0; 16]; // ok
 ///     });
 /// }
+/// ```
+///
+/// With the `std` feature, a call without a matching [`disable`] on the same
+/// thread is reported on stderr and ignored in debug builds. Release builds
+/// pass it on to the runtime.
 #[inline]
+#[track_caller]
 pub fn enable() {
-    #[cfg(rtsan_enabled)]
-    unsafe {
-        rtsan_standalone_sys::__rtsan_enable();
+    #[cfg(feature = "std")]
+    if !context::enable() {
+        return;
     }
+    backend::get().enable();
 }
 
 /// Initializes rtsan if it has not been initialized yet.
 /// Used by the RTSan runtime to ensure that rtsan is initialized before any
 /// other rtsan functions are called.
 ///
+/// When sanitizing is enabled, this is also called before `main` on Linux,
+/// the BSDs, macOS and iOS, if the linker keeps the constructor of this
+/// crate. Call it at the start of `main` to be sure.
+///
 /// # Example
 ///
 /// ```
@@ -145,11 +233,28 @@ pub fn enable() {
 ///     ensure_initialized();
 /// }
 /// ```
+///
+/// With the `std` feature, this also reads the `RTSAN_ACTIVE` environment
+/// variable, see [`set_active`].
 pub fn ensure_initialized() {
     #[cfg(rtsan_enabled)]
     unsafe {
         rtsan_standalone_sys::__rtsan_ensure_initialized();
     }
+    #[cfg(feature = "std")]
+    active::read_env();
+    #[cfg(all(rtsan_enabled, feature = "std"))]
+    unsafe {
+        rtsan_standalone_sys::__sanitizer_set_death_callback(Some(on_death));
+    }
+}
+
+/// Called by the runtime before it stops the process after a report.
+#[cfg(all(rtsan_enabled, feature = "std"))]
+extern "C" fn on_death() {
+    domain::on_death();
+    #[cfg(unix)]
+    violation::on_death();
 }
 
 /// Allows the user to specify a function as not-real-time-safe
@@ -169,13 +274,11 @@ pub fn ensure_initialized() {
 /// #[blocking]
 /// fn my_blocking_function_preferred() {}
 /// ```
-#[allow(unused_variables)]
+///
+/// The call is not reported if the current [`Domain`] allows it.
 pub fn notify_blocking_call(function_name: &'static core::ffi::CStr) {
-    #[cfg(rtsan_enabled)]
-    {
-        unsafe {
-            rtsan_standalone_sys::__rtsan_notify_blocking_call(function_name.as_ptr());
-        }
+    if !domain::allows(function_name.to_bytes()) {
+        backend::get().notify_blocking_call(function_name);
     }
 }
 
@@ -196,7 +299,7 @@ pub fn notify_blocking_call(function_name: &'static core::ffi::CStr) {
 #[macro_export]
 macro_rules! scoped_disabler {
     ($($body:tt)*) => {{
-        let __guard = rtsan_standalone::ScopedDisabler::default();
+        let __guard = $crate::ScopedDisabler::default();
         $($body)*
     }};
 }
@@ -204,7 +307,7 @@ macro_rules! scoped_disabler {
 /// Enter real-time context for the lifetime of the object.
 /// When in a real-time context, RTSan interceptors will error if realtime
 /// violations are detected.
-/// Corresponds to a [`nonblocking`] macro.
+/// Corresponds to a [`nonblocking`] or [`realtime`] macro.
 ///
 /// # Example
 ///
@@ -218,19 +321,177 @@ macro_rules! scoped_disabler {
 ///     }
 ///     let _ = vec![0.0; 256]; // ok
 /// }
+///
+/// // Macro usage preferred
+/// fn process_preferred() {
+///     realtime! {
+///         let _ = vec![0.0; 256]; // not ok
+///     };
+///     let _ = vec![0.0; 256]; // ok
+/// }
+/// ```
+///
+/// The guard is `!Send`, the context belongs to the thread that entered it.
+///
+/// ```compile_fail
+/// let guard = rtsan_standalone::ScopedSanitizeRealtime::default();
+/// std::thread::spawn(move || drop(guard));
 /// ```
-pub struct ScopedSanitizeRealtime;
+///
+/// `#[must_use]` does not catch `let _ = ScopedSanitizeRealtime::default();`,
+/// which drops the guard and exits the context right away. Bind it to a name
+/// like `_guard` instead.
+#[must_use = "the real-time context is exited when the guard is dropped"]
+pub struct ScopedSanitizeRealtime {
+    /// Sanitizing was active when the guard was created, see [`set_active`].
+    entered: bool,
+    /// Restores the previous domain after the context is exited.
+    _domain: Option<domain::Current>,
+    /// The scope to restore, set by [`with_scope`](Self::with_scope).
+    #[cfg(feature = "std")]
+    previous_scope: Option<Option<&'static str>>,
+    #[cfg(feature = "std")]
+    owner: context::Owner,
+    _not_send: PhantomData<*const ()>,
+}
 
 impl Default for ScopedSanitizeRealtime {
     fn default() -> Self {
-        realtime_enter();
-        Self
+        let entered = is_active();
+        if entered {
+            sys_realtime_enter();
+        }
+        Self::new(entered, None)
     }
 }
 
 impl Drop for ScopedSanitizeRealtime {
     fn drop(&mut self) {
-        realtime_exit();
+        #[cfg(feature = "std")]
+        self.owner.check("ScopedSanitizeRealtime");
+        if self.entered {
+            sys_realtime_exit();
+        }
+        #[cfg(feature = "std")]
+        if let Some(previous) = self.previous_scope {
+            context::replace_scope(previous);
+        }
+    }
+}
+
+impl ScopedSanitizeRealtime {
+    /// Enters the real-time context for about one in `rate` calls, chosen by
+    /// a pseudo-random number generator per thread. Sampling keeps the
+    /// overhead of long running sanitized builds low.
+    /// Corresponds to `#[nonblocking(sample = 1/rate)]`.
+    ///
+    /// The `RTSAN_SAMPLE_RATE` environment variable overrides `rate` of all
+    /// sampled scopes, `RTSAN_SAMPLE_RATE=1` sanitizes every call. It is read
+    /// once, and only with the `std` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::*;
+    ///
+    /// fn process() {
+    ///     let _guard = ScopedSanitizeRealtime::sampled(100);
+    ///     let _ = vec![0.0; 256]; // not ok, reported in one of 100 calls
+    /// }
+    /// ```
+    ///
+    /// A rate of `0` or `1` sanitizes every call. The macro rejects `1/0`.
+    ///
+    /// ```compile_fail
+    /// #[rtsan_standalone::nonblocking(sample = 1/0)]
+    /// fn process() {}
+    /// ```
+    #[inline]
+    #[must_use = "the real-time context is exited when the guard is dropped"]
+    pub fn sampled(rate: u32) -> Option<Self> {
+        if cfg!(rtsan_enabled) && !sample::hit(rate) {
+            return None;
+        }
+        Some(Self::default())
+    }
+
+    /// Enters the real-time context as part of the [`Domain`] called `name`,
+    /// unless the domain is disabled or its sample rate skips this scope.
+    /// While entered, the domain is current on this thread, its allowlist
+    /// applies and reports of the runtime name it.
+    /// Corresponds to `#[nonblocking(domain = "name")]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::*;
+    ///
+    /// fn process() {
+    ///     let _guard = ScopedSanitizeRealtime::for_domain("audio");
+    ///     let _ = vec![0.0; 256]; // not ok, unless "audio" is disabled
+    /// }
+    /// ```
+    #[inline]
+    pub fn for_domain(name: &'static str) -> Self {
+        Self::in_domain(Domain::get(name))
+    }
+
+    /// Like [`for_domain`](Self::for_domain), without looking up the name
+    /// of the domain again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::*;
+    ///
+    /// let audio = Domain::get("audio");
+    /// for _ in 0..4 {
+    ///     let _guard = ScopedSanitizeRealtime::in_domain(audio);
+    /// }
+    /// ```
+    #[inline]
+    pub fn in_domain(domain: Domain) -> Self {
+        let entered = is_active() && domain.hit();
+        if !entered {
+            return Self::new(entered, None);
+        }
+        let current = domain::Current::enter(domain);
+        sys_realtime_enter();
+        Self::new(entered, Some(current))
+    }
+
+    /// Names the scope for [`current_scope`] while the guard is alive, if it
+    /// entered the real-time context. The [`nonblocking`] macro names its
+    /// scope after the function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::*;
+    ///
+    /// let _guard = ScopedSanitizeRealtime::default().with_scope("mixer");
+    /// assert_eq!(current_scope(), Some("mixer"));
+    /// ```
+    #[inline]
+    #[cfg_attr(not(feature = "std"), allow(unused_mut, unused_variables))]
+    pub fn with_scope(mut self, name: &'static str) -> Self {
+        #[cfg(feature = "std")]
+        if self.entered && self.previous_scope.is_none() {
+            self.previous_scope = Some(context::replace_scope(Some(name)));
+        }
+        self
+    }
+
+    fn new(entered: bool, domain: Option<domain::Current>) -> Self {
+        Self {
+            entered,
+            _domain: domain,
+            #[cfg(feature = "std")]
+            previous_scope: None,
+            #[cfg(feature = "std")]
+            owner: context::Owner::current(),
+            _not_send: PhantomData,
+        }
     }
 }
 
@@ -261,17 +522,300 @@ impl Drop for ScopedSanitizeRealtime {
 ///     let mut data = vec![0.0; 16]; // not ok
 /// }
 /// ```
-pub struct ScopedDisabler;
+///
+/// The guard is `!Send` and must be bound to a name, like
+/// [`ScopedSanitizeRealtime`].
+#[must_use = "error reporting is enabled again when the guard is dropped"]
+pub struct ScopedDisabler {
+    #[cfg(feature = "std")]
+    owner: context::Owner,
+    _not_send: PhantomData<*const ()>,
+}
 
 impl Default for ScopedDisabler {
     fn default() -> Self {
         disable();
-        Self
+        Self {
+            #[cfg(feature = "std")]
+            owner: context::Owner::current(),
+            _not_send: PhantomData,
+        }
     }
 }
 
 impl Drop for ScopedDisabler {
     fn drop(&mut self) {
+        #[cfg(feature = "std")]
+        self.owner.check("ScopedDisabler");
         enable();
     }
 }
+
+/// Wraps a future so that it is in a real-time context only while it is
+/// being polled. The context is exited again before `poll` returns, so the
+/// executor never runs unrelated tasks in a real-time context.
+/// Corresponds to a [`nonblocking`] macro on an `async fn`.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// async fn process() {
+///     RealtimeFuture::new(async {
+///         let _ = vec![0.0; 256]; // not ok
+///     })
+///     .await;
+///     let _ = vec![0.0; 256]; // ok
+/// }
+///
+/// // Macro usage preferred
+/// #[nonblocking]
+/// async fn process_preferred() {
+///     let _ = vec![0.0; 256]; // not ok
+/// }
+/// ```
+pub struct RealtimeFuture<F> {
+    future: F,
+    domain: Option<Domain>,
+    scope: Option<&'static str>,
+}
+
+impl<F> RealtimeFuture<F> {
+    /// Polls `future` in a real-time context, like
+    /// [`ScopedSanitizeRealtime::default`] around every call to `poll`.
+    pub fn new(future: F) -> Self {
+        Self {
+            future,
+            domain: None,
+            scope: None,
+        }
+    }
+
+    /// Polls the future as part of a [`Domain`], see
+    /// [`ScopedSanitizeRealtime::for_domain`].
+    pub fn with_domain(future: F, name: &'static str) -> Self {
+        Self::in_domain(future, Domain::get(name))
+    }
+
+    /// Polls the future as part of `domain`, see
+    /// [`ScopedSanitizeRealtime::in_domain`].
+    pub fn in_domain(future: F, domain: Domain) -> Self {
+        Self {
+            future,
+            domain: Some(domain),
+            scope: None,
+        }
+    }
+
+    /// Names the scope while the future is polled, see
+    /// [`ScopedSanitizeRealtime::with_scope`].
+    pub fn with_scope(mut self, name: &'static str) -> Self {
+        self.scope = Some(name);
+        self
+    }
+}
+
+impl<F: Future> Future for RealtimeFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (domain, scope) = (self.domain, self.scope);
+        // SAFETY: `future` is structurally pinned, it is never moved out of `self`.
+        let future = unsafe { self.map_unchecked_mut(|this| &mut this.future) };
+        let guard = match domain {
+            Some(domain) => ScopedSanitizeRealtime::in_domain(domain),
+            None => ScopedSanitizeRealtime::default(),
+        };
+        let _guard = match scope {
+            Some(name) => guard.with_scope(name),
+            None => guard,
+        };
+        future.poll(cx)
+    }
+}
+
+/// Wraps an implementation of a trait marked with [`nonblocking`], so that
+/// every call through the wrapper is in a real-time context, whether or not
+/// the implementation itself is annotated. The macro implements the trait
+/// for `Sanitized<P>` by forwarding to `P`. Calls on an implementation the
+/// caller did not wrap are only sanitized if the implementation is annotated.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// #[nonblocking]
+/// trait Processor {
+///     fn process(&mut self, buffer: &mut [f32]);
+/// }
+///
+/// struct Amplifier;
+///
+/// impl Processor for Amplifier {
+///     fn process(&mut self, buffer: &mut [f32]) {
+///         let _ = vec![0.0; buffer.len()]; // not ok
+///     }
+/// }
+///
+/// let processor: Box<dyn Processor> = Box::new(Sanitized(Amplifier));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sanitized<P>(pub P);
+
+/// Adds the test attributes to a test with `#[rtsan_standalone::testing::test]`,
+/// which ignore it unless this build is sanitized.
+#[cfg(all(feature = "testing", rtsan_enabled))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test {
+    ($($item:tt)*) => {
+        #[::core::prelude::v1::test]
+        $($item)*
+    };
+}
+
+#[cfg(all(feature = "testing", not(rtsan_enabled), rtsan_supported))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test {
+    ($($item:tt)*) => {
+        #[::core::prelude::v1::test]
+        #[ignore = "RealtimeSanitizer is disabled, build with RTSAN_ENABLE=1 to run this test"]
+        $($item)*
+    };
+}
+
+#[cfg(all(feature = "testing", not(rtsan_supported)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test {
+    ($($item:tt)*) => {
+        #[::core::prelude::v1::test]
+        #[ignore = "RealtimeSanitizer is not supported on this target"]
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    pub use crate::domain::DomainCell;
+
+    /// Runs the body of an `extern "C"` function with `#[nonblocking]`. A
+    /// panic must not unwind into the caller, so it is caught and `fallback`
+    /// returns the value instead. The panic path is not reported as a
+    /// violation, see `install_panic_hook`.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn ffi_call<R>(body: impl FnOnce() -> R, fallback: impl FnOnce() -> R) -> R {
+        crate::panic::ffi_enter();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(body));
+        crate::panic::ffi_exit();
+        result.unwrap_or_else(|_| fallback())
+    }
+
+    /// Without `std` panics can not be caught, the body runs as is.
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub fn ffi_call<R>(body: impl FnOnce() -> R, _fallback: impl FnOnce() -> R) -> R {
+        body()
+    }
+
+    /// The fallback of an `extern "C"` function without `fallback = ...`.
+    #[cfg(feature = "std")]
+    pub fn ffi_abort<R>() -> R {
+        std::process::abort()
+    }
+
+    /// Never called, without `std` panics are not caught.
+    #[cfg(not(feature = "std"))]
+    pub fn ffi_abort<R>() -> R {
+        unreachable!()
+    }
+
+    /// What a test with `#[rtsan_standalone::testing::test]` expects.
+    #[cfg(feature = "testing")]
+    pub enum Expect {
+        /// A violation in the function with this name.
+        Violation(&'static str),
+        Clean,
+    }
+
+    /// Runs the body of a test with `#[rtsan_standalone::testing::test]`.
+    #[cfg(feature = "testing")]
+    #[allow(unused_variables)]
+    pub fn run_test(expect: Expect, test: impl FnOnce()) {
+        #[cfg(unix)]
+        crate::testing::run_test(expect, test);
+        #[cfg(not(unix))]
+        panic!("RealtimeSanitizer is not supported on this target");
+    }
+
+    /// Counts the calls of a function with `#[nonblocking(warmup = N)]`.
+    pub struct Warmup(AtomicUsize);
+
+    impl Warmup {
+        #[allow(clippy::new_without_default)]
+        pub const fn new() -> Self {
+            Self(AtomicUsize::new(0))
+        }
+
+        /// Returns `true` once `calls` calls have been made before this one.
+        /// The counter stops growing once it reaches `calls`, so it never
+        /// overflows.
+        #[inline]
+        pub fn done(&self, calls: usize) -> bool {
+            self.0.load(Ordering::Relaxed) >= calls
+                || self.0.fetch_add(1, Ordering::Relaxed) >= calls
+        }
+    }
+
+    /// Drops `value` and reports the drop if the type has drop glue, used by
+    /// `#[nonblocking(drops = "strict")]` for explicit `drop` calls.
+    #[inline]
+    pub fn drop_in_realtime<T>(value: T) {
+        if core::mem::needs_drop::<T>() {
+            notify_blocking_call(&["drop of ", core::any::type_name::<T>()]);
+        }
+        drop(value);
+    }
+
+    /// Assigns `value` to `place` and reports the drop of the previous value
+    /// like [`drop_in_realtime`], used by `#[nonblocking(drops = "strict")]`
+    /// for assignments.
+    #[inline]
+    pub fn replace_in_realtime<T>(place: &mut T, value: T) {
+        drop_in_realtime(core::mem::replace(place, value));
+    }
+
+    /// Reports a blocking call with a name joined from `parts`, used by the
+    /// [`blocking`](crate::blocking) macro. The name is assembled on the
+    /// stack, so no allocation happens before the report. Calls allowed by
+    /// the current [`Domain`](crate::Domain) are not reported.
+    #[inline]
+    #[allow(unused_variables)]
+    pub fn notify_blocking_call(parts: &[&str]) {
+        #[cfg(any(rtsan_enabled, feature = "backend"))]
+        {
+            let mut name = [0u8; 512];
+            let bytes = parts.iter().flat_map(|part| part.bytes());
+            // the last byte stays as nul terminator
+            let end = name.len() - 1;
+            for (dst, byte) in name[..end].iter_mut().zip(bytes.filter(|&b| b != 0)) {
+                *dst = byte;
+            }
+            let len = name.iter().position(|&b| b == 0).unwrap_or(end);
+            if crate::domain::allows(&name[..len]) {
+                return;
+            }
+            if let Ok(name) = core::ffi::CStr::from_bytes_until_nul(&name) {
+                #[cfg(not(feature = "backend"))]
+                use crate::backend::Backend as _;
+                crate::backend::get().notify_blocking_call(name);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use core::any::Any;
+#[cfg(rtsan_enabled)]
+use core::fmt::{self, Write};
+use core::panic::Location;
+use std::cell::Cell;
+#[cfg(rtsan_enabled)]
+use std::string::String;
+use std::sync::Once;
+
+use crate::{context, enable, ScopedDisabler};
+
+thread_local! {
+    /// Number of `extern "C"` functions with panic containment on this thread.
+    static FFI_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Set when the panic hook disabled the sanitizer for the panic path.
+    static PANIC_DISABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Installs a panic hook for panics in a real-time context.
+///
+/// Formatting and printing the message, boxing the payload and unwinding all
+/// allocate, which would otherwise be reported instead of the panic. The hook
+/// reports the panic itself as a violation, naming the message and location,
+/// and disables the sanitizer for the rest of the panic path. Sanitizing is
+/// enabled again once the thread leaves the real-time context. Panics outside
+/// of a real-time context are passed to the previous hook unchanged.
+///
+/// Installed automatically by `#[nonblocking]` functions with an
+/// `extern "C"` ABI. Call it once at startup, after any other panic hook is
+/// set, as the hook forwards to the previous one.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// #[nonblocking]
+/// fn process(data: &[f32]) {
+///     assert_eq!(data.len(), 16); // reported as a panic in real-time context
+/// }
+///
+/// fn main() {
+///     ensure_initialized();
+///     install_panic_hook();
+///     process(&[0.0; 16]);
+/// }
+/// ```
+pub fn install_panic_hook() {
+    INSTALL_HOOK.call_once(|| {
+        // the hook itself is allocated, possibly from a real-time context
+        let _guard = ScopedDisabler::default();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let in_realtime = context::is_realtime() && !context::is_disabled();
+            if (in_realtime || FFI_DEPTH.get() > 0) && !PANIC_DISABLED.get() {
+                crate::disable();
+                PANIC_DISABLED.set(true);
+                if in_realtime {
+                    // an `extern "C"` function catches the panic and returns
+                    // its fallback, the process goes on
+                    let contained = FFI_DEPTH.get() > 0;
+                    report(info.location(), info.payload(), contained);
+                }
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Reports the panic with the kind `panic`, in the format of the runtime, so
+/// that `report::parse` and the violation handler see it. Like a report of
+/// the runtime, it stops the process unless `halt_on_error` is turned off or
+/// the panic is `contained` by an `extern "C"` function.
+#[cfg_attr(not(rtsan_enabled), allow(unused_variables))]
+fn report(location: Option<&Location<'_>>, payload: &(dyn Any + Send), contained: bool) {
+    #[cfg(rtsan_enabled)]
+    {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        let mut header = StackWriter::default();
+        let _ = write!(
+            header,
+            "=={}==ERROR: RealtimeSanitizer: panic",
+            std::process::id()
+        );
+        let mut panic = StackWriter::default();
+        let _ = write!(panic, "Panic `{message}`");
+        let mut summary = StackWriter::default();
+        let _ = write!(summary, "SUMMARY: RealtimeSanitizer: panic");
+        if let Some(location) = location {
+            let _ = write!(panic, " at {location}");
+            let _ = write!(summary, " {location}");
+        }
+        let _ = write!(panic, " in real-time context!");
+        for line in [header.as_str(), panic.as_str(), summary.as_str()] {
+            crate::report::write(line.as_bytes());
+            crate::report::write(b"\n");
+        }
+        crate::domain::write_domain();
+        #[cfg(unix)]
+        if !contained && crate::violation::halts_on_error() {
+            crate::violation::on_death();
+            // SAFETY: exits like the runtime after a report, without unwinding.
+            unsafe { libc::_exit(1) };
+        }
+    }
+}
+
+/// Formats into a fixed buffer on the stack, truncating what does not fit.
+#[cfg(rtsan_enabled)]
+struct StackWriter {
+    buffer: [u8; 448],
+    len: usize,
+}
+
+#[cfg(rtsan_enabled)]
+impl Default for StackWriter {
+    fn default() -> Self {
+        Self {
+            buffer: [0; 448],
+            len: 0,
+        }
+    }
+}
+
+#[cfg(rtsan_enabled)]
+impl StackWriter {
+    fn as_str(&self) -> &str {
+        // only whole characters are copied in `write_str`
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or_default()
+    }
+}
+
+#[cfg(rtsan_enabled)]
+impl Write for StackWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(self.buffer.len() - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        for byte in s[..end].bytes() {
+            // the report is a single line
+            self.buffer[self.len] = if byte == b'\n' { b' ' } else { byte };
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Called once the thread left all real-time contexts. Re-enables the
+/// sanitizer if the panic hook disabled it.
+pub(crate) fn realtime_left() {
+    if FFI_DEPTH.try_with(Cell::get).unwrap_or(0) == 0
+        && PANIC_DISABLED.try_with(|disabled| disabled.replace(false)) == Ok(true)
+    {
+        enable();
+    }
+}
+
+/// Called before the `catch_unwind` of an `extern "C"` function.
+pub(crate) fn ffi_enter() {
+    install_panic_hook();
+    FFI_DEPTH.set(FFI_DEPTH.get() + 1);
+}
+
+/// Called after the `catch_unwind` of an `extern "C"` function. Re-enables
+/// the sanitizer if the panic hook disabled it.
+pub(crate) fn ffi_exit() {
+    FFI_DEPTH.set(FFI_DEPTH.get() - 1);
+    if PANIC_DISABLED.replace(false) {
+        enable();
+    }
+}
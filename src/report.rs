@@ -0,0 +1,284 @@
+//! Parses the reports RTSan prints, for tools that work with its output.
+//!
+//! ```text
+//! ==283082==ERROR: RealtimeSanitizer: unsafe-library-call
+//! Intercepted call to real-time unsafe function `calloc` in real-time context!
+//!     #0 0x55c0c3be8cf2 in calloc /tmp/llvm-project/compiler-rt/lib/rtsan/rtsan_interceptors_posix.cpp:470:34
+//!     #1 0x55c0c3be4e69 in alloc::alloc::alloc_zeroed::hf760e6484fdf32c8 /rustc/library/alloc/src/alloc.rs:170:14
+//!     #2 0x55c0c3be2ab4 in _start (target/debug/examples/vector+0x2ab4) (BuildId: adb992a7e560cd00)
+//!     #3 0x7f3a5c429d8f  (/lib/x86_64-linux-gnu/libc.so.6+0x29d8f)
+//! SUMMARY: RealtimeSanitizer: unsafe-library-call /rustc/library/alloc/src/alloc.rs:170:14 in alloc::alloc::alloc_zeroed::hf760e6484fdf32c8
+//! ```
+//!
+//! With the `serde` feature, the types implement `Serialize` and
+//! `Deserialize`.
+
+use core::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A report of a violation, see [`parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Report {
+    /// The process id from the `==PID==ERROR:` line.
+    pub pid: Option<u32>,
+    pub kind: ViolationKind,
+    /// The intercepted function, the name of the blocking function, or the
+    /// message of a panic.
+    pub function: String,
+    pub frames: Vec<Frame>,
+    /// The `SUMMARY:` line without its prefix, `None` if the report was cut
+    /// off before it.
+    pub summary: Option<String>,
+    /// The [`Domain`](crate::Domain) named by the line this crate prints
+    /// after the report when the runtime stops the process.
+    pub domain: Option<String>,
+}
+
+/// The kind of a violation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// `unsafe-library-call`, an intercepted call like `malloc`.
+    UnsafeLibraryCall,
+    /// `blocking-call`, a function marked with [`blocking`](crate::blocking).
+    BlockingCall,
+    /// `panic`, a panic in a real-time context, reported by the hook of
+    /// [`install_panic_hook`](crate::install_panic_hook).
+    Panic,
+    /// A kind this crate does not know yet.
+    Other(String),
+}
+
+impl ViolationKind {
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "unsafe-library-call" => ViolationKind::UnsafeLibraryCall,
+            "blocking-call" => ViolationKind::BlockingCall,
+            "panic" => ViolationKind::Panic,
+            kind => ViolationKind::Other(kind.into()),
+        }
+    }
+}
+
+/// The name RTSan prints, like `unsafe-library-call`.
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ViolationKind::UnsafeLibraryCall => "unsafe-library-call",
+            ViolationKind::BlockingCall => "blocking-call",
+            ViolationKind::Panic => "panic",
+            ViolationKind::Other(kind) => kind,
+        })
+    }
+}
+
+/// A frame of the stack trace of a [`Report`]. Symbolized frames have a
+/// function and usually a source location, unsymbolized frames only a module
+/// and an offset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Frame {
+    /// The `N` of `#N`.
+    pub index: usize,
+    pub address: u64,
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// The binary or library containing the frame, from `(module+0xoffset)`.
+    pub module: Option<String>,
+    pub offset: Option<u64>,
+    /// The line as printed, without indentation.
+    pub raw: String,
+}
+
+impl Frame {
+    /// Parses a frame line like `#0 0x55c0c3be8cf2 in calloc file.cpp:470:34`.
+    pub fn parse(line: &str) -> Option<Frame> {
+        let raw = line.trim();
+        let rest = raw.strip_prefix('#')?;
+        let (index, rest) = rest.split_once(' ')?;
+        let index = index.parse().ok()?;
+        let rest = rest.trim_start();
+        let (address, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        let address = parse_hex(address)?;
+
+        let mut frame = Frame {
+            index,
+            address,
+            function: None,
+            file: None,
+            line: None,
+            column: None,
+            module: None,
+            offset: None,
+            raw: raw.into(),
+        };
+        let mut rest = rest.trim();
+        if let Some(start) = rest.find(" (BuildId: ") {
+            rest = &rest[..start];
+        }
+        if let Some(function) = rest.strip_prefix("in ") {
+            rest = function;
+            // The function name runs to the end of the line and may contain
+            // spaces, a location after it does not.
+            if let Some((function, location)) = rest.rsplit_once(' ') {
+                if location.starts_with('(') && location.ends_with(')') {
+                    frame.set_module(location);
+                    rest = function;
+                } else if is_location(location) {
+                    frame.set_location(location);
+                    rest = function;
+                }
+            }
+            frame.function = Some(rest.trim().into());
+        } else if rest.starts_with('(') {
+            frame.set_module(rest);
+        }
+        Some(frame)
+    }
+
+    /// `file:line:column`, `file:line` or `file`.
+    fn set_location(&mut self, location: &str) {
+        let mut file = location;
+        let mut numbers = Vec::new();
+        while numbers.len() < 2 {
+            let Some((head, number)) = file.rsplit_once(':') else {
+                break;
+            };
+            let Ok(number) = number.parse::<u32>() else {
+                break;
+            };
+            numbers.push(number);
+            file = head;
+        }
+        match numbers[..] {
+            [column, line] => (self.line, self.column) = (Some(line), Some(column)),
+            [line] => self.line = Some(line),
+            _ => {}
+        }
+        self.file = Some(file.into());
+    }
+
+    /// `(module+0xoffset)`.
+    fn set_module(&mut self, module: &str) {
+        let module = module.trim_start_matches('(').trim_end_matches(')');
+        match module.rsplit_once('+') {
+            Some((name, offset)) => {
+                self.module = Some(name.into());
+                self.offset = parse_hex(offset);
+            }
+            None => self.module = Some(module.into()),
+        }
+    }
+}
+
+/// Returns `true` for `file:line`, `file:line:column` or a path, which tells
+/// a location apart from the last word of a function name.
+fn is_location(location: &str) -> bool {
+    let line = location
+        .rsplit_once(':')
+        .is_some_and(|(file, number)| !file.is_empty() && number.parse::<u32>().is_ok());
+    line || (location.contains('/') && !location.contains("::"))
+}
+
+fn parse_hex(number: &str) -> Option<u64> {
+    u64::from_str_radix(number.strip_prefix("0x")?, 16).ok()
+}
+
+/// Parses every report in `text`, which can contain other output between
+/// reports. Lines of a report that are not part of the format are skipped,
+/// so reports stay intact when other output is interleaved with them. A
+/// report without a `SUMMARY:` line ends at the next report.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::report::{self, ViolationKind};
+///
+/// let output = "\
+/// ==1==ERROR: RealtimeSanitizer: blocking-call
+/// Call to blocking function `my_app::load` in real-time context!
+///     #0 0x55c0c3be8cf2 in my_app::load src/main.rs:4:5
+/// SUMMARY: RealtimeSanitizer: blocking-call src/main.rs:4:5 in my_app::load
+/// ";
+/// let reports = report::parse(output);
+/// assert_eq!(reports[0].kind, ViolationKind::BlockingCall);
+/// assert_eq!(reports[0].function, "my_app::load");
+/// assert_eq!(reports[0].frames[0].line, Some(4));
+/// ```
+pub fn parse(text: &str) -> Vec<Report> {
+    let mut reports: Vec<Report> = Vec::new();
+    let mut current: Option<Report> = None;
+    for line in text.lines() {
+        if let Some(domain) = parse_domain(line) {
+            let report = current.as_mut().or(reports.last_mut());
+            if let Some(report) = report.filter(|report| report.domain.is_none()) {
+                report.domain = Some(domain.into());
+            }
+            continue;
+        }
+        if let Some((prefix, kind)) = line.split_once("ERROR: RealtimeSanitizer: ") {
+            reports.extend(current.take());
+            let pid = prefix.trim_matches('=').parse().ok();
+            current = Some(Report {
+                pid,
+                kind: ViolationKind::parse(kind.trim()),
+                function: String::new(),
+                frames: Vec::new(),
+                summary: None,
+                domain: None,
+            });
+            continue;
+        }
+        let Some(report) = current.as_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if let Some(summary) = trimmed.strip_prefix("SUMMARY: ") {
+            report.summary = Some(summary.into());
+            reports.extend(current.take());
+        } else if trimmed.starts_with('#') {
+            report.frames.extend(Frame::parse(trimmed));
+        } else if report.function.is_empty() && trimmed.ends_with("in real-time context!") {
+            if let Some((_, quoted)) = trimmed.split_once('`') {
+                if let Some((function, _)) = quoted.rsplit_once('`') {
+                    report.function = function.into();
+                }
+            }
+        }
+    }
+    reports.extend(current);
+    reports
+}
+
+/// The line naming the domain of a report, see [`Report::domain`].
+pub(crate) const DOMAIN_LINE: (&str, &str) =
+    ("RealtimeSanitizer: real-time domain `", "` was active");
+
+/// The domain of a line printed with [`DOMAIN_LINE`].
+pub(crate) fn parse_domain(line: &str) -> Option<&str> {
+    let (prefix, suffix) = DOMAIN_LINE;
+    line.trim().strip_prefix(prefix)?.strip_suffix(suffix)
+}
+
+/// Writes `bytes` where the runtime writes its reports, so they stay in
+/// order with them.
+#[cfg(rtsan_enabled)]
+pub(crate) fn write(bytes: &[u8]) {
+    #[cfg(unix)]
+    crate::violation::write_report(bytes);
+    #[cfg(not(unix))]
+    {
+        use std::io::Write;
+        let _ = std::io::stderr().write_all(bytes);
+    }
+}
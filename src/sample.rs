@@ -0,0 +1,84 @@
+//! Chooses the calls of [`ScopedSanitizeRealtime::sampled`] that are
+//! sanitized.
+//!
+//! [`ScopedSanitizeRealtime::sampled`]: crate::ScopedSanitizeRealtime::sampled
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Environment variable overriding the rate of all sampled scopes.
+#[cfg(feature = "std")]
+const RATE_VAR: &str = "RTSAN_SAMPLE_RATE";
+
+/// Returns `true` for about one in `rate` calls, or in the rate set with
+/// `RTSAN_SAMPLE_RATE`.
+pub(crate) fn hit(rate: u32) -> bool {
+    hit_exact(override_rate().unwrap_or(rate))
+}
+
+/// Returns `true` for about one in `rate` calls, ignoring
+/// `RTSAN_SAMPLE_RATE`. Used by domains, whose own rate wins.
+pub(crate) fn hit_exact(rate: u32) -> bool {
+    rate <= 1 || next() < u32::MAX / rate
+}
+
+/// The override from [`RATE_VAR`], read on first use. `0` means not read
+/// yet and `u32::MAX` means not set.
+#[cfg(feature = "std")]
+fn override_rate() -> Option<u32> {
+    static RATE: AtomicU32 = AtomicU32::new(0);
+
+    let mut rate = RATE.load(Ordering::Relaxed);
+    if rate == 0 {
+        // reading the environment allocates, possibly in a real-time context
+        let _disabler = crate::ScopedDisabler::default();
+        rate = std::env::var(RATE_VAR)
+            .ok()
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .map_or(u32::MAX, |rate| rate.clamp(1, u32::MAX - 1));
+        RATE.store(rate, Ordering::Relaxed);
+    }
+    (rate != u32::MAX).then_some(rate)
+}
+
+#[cfg(not(feature = "std"))]
+fn override_rate() -> Option<u32> {
+    None
+}
+
+/// A xorshift generator per thread, seeded with the address of its state.
+#[cfg(feature = "std")]
+fn next() -> u32 {
+    use core::cell::Cell;
+
+    std::thread_local! {
+        static STATE: Cell<u32> = const { Cell::new(0) };
+    }
+
+    STATE.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            x = state as *const Cell<u32> as usize as u32 | 1;
+        }
+        x = xorshift(x);
+        state.set(x);
+        x
+    })
+}
+
+/// Without thread locals a single generator is shared, races between
+/// threads only make it less predictable.
+#[cfg(not(feature = "std"))]
+fn next() -> u32 {
+    static STATE: AtomicU32 = AtomicU32::new(0x9e37_79b9);
+
+    let x = xorshift(STATE.load(Ordering::Relaxed));
+    STATE.store(x, Ordering::Relaxed);
+    x
+}
+
+fn xorshift(mut x: u32) -> u32 {
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
@@ -0,0 +1,115 @@
+use core::fmt;
+
+/// Whether this build is sanitized, see [`status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Status {
+    /// Built with `RTSAN_ENABLE` on a supported target.
+    Enabled {
+        /// LLVM version of the runtime, `None` for a library given with
+        /// `RTSAN_LIB_PATH`.
+        runtime_version: Option<&'static str>,
+        link_mode: LinkMode,
+    },
+    /// Built without `RTSAN_ENABLE`.
+    DisabledByEnvironment,
+    /// RTSan does not support the target with this triple.
+    UnsupportedTarget(&'static str),
+}
+
+/// How the RTSan runtime is linked into the binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkMode {
+    /// A static library, on Linux.
+    Static,
+    /// A dynamic library, on Apple platforms.
+    Dynamic,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Enabled {
+                runtime_version,
+                link_mode,
+            } => {
+                let link_mode = match link_mode {
+                    LinkMode::Static => "static",
+                    LinkMode::Dynamic => "dynamic",
+                };
+                write!(
+                    f,
+                    "RealtimeSanitizer enabled, {link_mode} runtime {}",
+                    runtime_version.unwrap_or("from RTSAN_LIB_PATH")
+                )
+            }
+            Status::DisabledByEnvironment => {
+                f.write_str("RealtimeSanitizer disabled, build with RTSAN_ENABLE=1 to enable it")
+            }
+            Status::UnsupportedTarget(target) => {
+                write!(f, "RealtimeSanitizer is not supported on target {target}")
+            }
+        }
+    }
+}
+
+/// Returns whether sanitizing was enabled when this crate was built, as
+/// decided by its build script.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// match status() {
+///     Status::Enabled { runtime_version, .. } => println!("sanitized with {runtime_version:?}"),
+///     Status::DisabledByEnvironment => println!("not sanitized"),
+///     Status::UnsupportedTarget(target) => println!("{target} is not supported"),
+///     _ => {}
+/// }
+/// ```
+pub const fn status() -> Status {
+    #[cfg(rtsan_enabled)]
+    {
+        let link_mode = match rtsan_standalone_sys::LINK_MODE.as_bytes() {
+            b"static" => LinkMode::Static,
+            _ => LinkMode::Dynamic,
+        };
+        Status::Enabled {
+            runtime_version: rtsan_standalone_sys::RUNTIME_VERSION,
+            link_mode,
+        }
+    }
+    #[cfg(all(not(rtsan_enabled), rtsan_supported))]
+    {
+        Status::DisabledByEnvironment
+    }
+    #[cfg(all(not(rtsan_enabled), not(rtsan_supported)))]
+    {
+        Status::UnsupportedTarget(env!("RTSAN_TARGET"))
+    }
+}
+
+/// Aborts the process with a message if sanitizing is not enabled, see
+/// [`status`]. Jobs that rely on sanitizing call it at startup, so they fail
+/// instead of silently running unsanitized.
+///
+/// # Example
+///
+/// ```no_run
+/// // at the start of `main`
+/// rtsan_standalone::require_enabled();
+/// ```
+pub fn require_enabled() {
+    let status = status();
+    if matches!(status, Status::Enabled { .. }) {
+        return;
+    }
+    #[cfg(feature = "std")]
+    {
+        std::eprintln!("rtsan-standalone: sanitizing is required, but {status}");
+        std::process::abort();
+    }
+    #[cfg(not(feature = "std"))]
+    panic!("rtsan-standalone: sanitizing is required, but {status}");
+}
@@ -0,0 +1,228 @@
+//! Tests for code that must be flagged by RTSan.
+//!
+//! The runtime stops the process on the first violation, so a test can not
+//! check for a violation in its own process. [`catch_violation`] runs the
+//! code in a forked child instead and reads the child's report through a
+//! pipe. The [`test`] attribute does the same for a whole test.
+//!
+//! The attribute is not exported at the crate root, where
+//! `use rtsan_standalone::*` would make the built-in `#[test]` ambiguous.
+
+pub use rtsan_standalone_macros::test;
+
+#[cfg(unix)]
+pub use fork::catch_violation;
+#[cfg(unix)]
+pub(crate) use fork::run_test;
+
+#[cfg(unix)]
+mod fork {
+    use std::boxed::Box;
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+    use std::string::String;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, Once};
+    use std::vec::Vec;
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use crate::__private::Expect;
+    use crate::report::{self, Report};
+
+    /// Held from creating the pipes until the parent closed their write ends,
+    /// so that the child of a concurrent call does not inherit them and keep
+    /// them open.
+    static FORK: Mutex<()> = Mutex::new(());
+
+    static INSTALL_HOOK: Once = Once::new();
+
+    /// Set in a child, which the panic hook only checks.
+    static IN_CHILD: AtomicBool = AtomicBool::new(false);
+
+    /// Runs `f` in a real-time context in a forked child process and returns
+    /// its value, or the first violation reported while it ran.
+    ///
+    /// The value is serialized to get it out of the child, and changes `f`
+    /// makes to the memory of the process are lost with the child. Without
+    /// `RTSAN_ENABLE`, nothing is reported and this always returns the value.
+    ///
+    /// Only the calling thread is forked, so `f` must not wait on other
+    /// threads or on locks they may hold. This includes the allocator in a
+    /// multithreaded test runner, unless it is fork-safe like the one of
+    /// glibc. The child itself only allocates in `f` and to send the value.
+    ///
+    /// # Panics
+    ///
+    /// If the fork fails or the child exits without a value and a report,
+    /// for example because `f` panicked. The panic path of the child is not
+    /// sanitized, so the allocations of the panic are not reported instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rtsan_standalone::testing::catch_violation;
+    /// use rtsan_standalone::{status, Status};
+    ///
+    /// let sum = catch_violation(|| [1, 2, 3].iter().sum::<i32>());
+    /// assert_eq!(sum, Ok(6));
+    ///
+    /// let result = catch_violation(|| vec![0.0f32; 256].len());
+    /// if matches!(status(), Status::Enabled { .. }) {
+    ///     assert_eq!(result.unwrap_err().function, "malloc");
+    /// }
+    /// ```
+    // a test only builds the report once, its size does not matter
+    #[allow(clippy::result_large_err)]
+    pub fn catch_violation<T, F>(f: F) -> Result<T, Report>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> T,
+    {
+        crate::ensure_initialized();
+        install_hook();
+        let fork = FORK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (report_read, report_write) = pipe();
+        let (value_read, value_write) = pipe();
+
+        match unsafe { libc::fork() } {
+            -1 => panic!("rtsan-standalone: fork failed"),
+            0 => {
+                unsafe {
+                    libc::close(report_read);
+                    libc::close(value_read);
+                }
+                run_child(f, report_write, value_write)
+            }
+            child => {
+                unsafe {
+                    libc::close(report_write);
+                    libc::close(value_write);
+                }
+                drop(fork);
+                // read the value on another thread, so a child writing a large
+                // value does not block while we wait for the end of its report
+                let value = std::thread::spawn(move || read_to_end(value_read));
+                let output = String::from_utf8_lossy(&read_to_end(report_read)).into_owned();
+                let value = value.join().unwrap_or_default();
+                let mut status = 0;
+                unsafe {
+                    libc::waitpid(child, &mut status, 0);
+                }
+
+                if let Some(report) = report::parse(&output).into_iter().next() {
+                    return Err(report);
+                }
+                match serde_json::from_slice(&value) {
+                    Ok(value) => Ok(value),
+                    Err(_) => panic!(
+                        "rtsan-standalone: the child process exited with status {status} without a value or a report"
+                    ),
+                }
+            }
+        }
+    }
+
+    fn run_child<T, F>(f: F, report_fd: i32, value_fd: i32) -> !
+    where
+        T: Serialize,
+        F: FnOnce() -> T,
+    {
+        // Another thread may have held any lock at the fork, the child must
+        // not take one before running `f`.
+        IN_CHILD.store(true, Ordering::Relaxed);
+        #[cfg(rtsan_enabled)]
+        crate::violation::set_report_fd(report_fd);
+        #[cfg(not(rtsan_enabled))]
+        let _ = report_fd;
+
+        let value = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = crate::ScopedSanitizeRealtime::default();
+            f()
+        }));
+        // the child must never return into the caller, which would run the rest
+        // of the test a second time
+        let Ok(value) = value else {
+            unsafe { libc::_exit(101) }
+        };
+        let mut value_file = unsafe { File::from_raw_fd(value_fd) };
+        let written = serde_json::to_writer(&mut value_file, &value);
+        unsafe { libc::_exit(if written.is_ok() { 0 } else { 1 }) }
+    }
+
+    /// Installs a panic hook for the children before the first fork, setting
+    /// a hook takes a lock and allocates. Like `install_panic_hook`, the hook
+    /// disables the sanitizer for the rest of the panic path in a child, which
+    /// exits right after it.
+    fn install_hook() {
+        INSTALL_HOOK.call_once(|| {
+            let _disabler = crate::ScopedDisabler::default();
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                if IN_CHILD.load(Ordering::Relaxed) {
+                    crate::disable();
+                }
+                previous(info);
+            }));
+        });
+    }
+
+    /// A pipe whose ends are closed in programs executed by other threads.
+    fn pipe() -> (i32, i32) {
+        let mut fds = [0; 2];
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+        let result = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) };
+        // without `pipe2`, a concurrent `exec` can still see the ends between
+        // the two calls
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+        let result = unsafe {
+            let result = libc::pipe(fds.as_mut_ptr());
+            for fd in fds {
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+            result
+        };
+        if result != 0 {
+            panic!("rtsan-standalone: pipe failed");
+        }
+        (fds[0], fds[1])
+    }
+
+    fn read_to_end(fd: i32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let _ = unsafe { File::from_raw_fd(fd) }.read_to_end(&mut bytes);
+        bytes
+    }
+
+    /// Runs the body of a test with `#[rtsan_standalone::testing::test]` and
+    /// panics if the expectation is not met.
+    pub(crate) fn run_test(expect: Expect, test: impl FnOnce()) {
+        match (expect, catch_violation(test)) {
+            (Expect::Violation(function), Err(report)) if report.function == function => {}
+            (Expect::Violation(function), Err(report)) => panic!(
+                "expected a violation in `{function}`, but got a violation in `{}`\n{}",
+                report.function,
+                describe(&report)
+            ),
+            (Expect::Violation(function), Ok(())) => {
+                panic!("expected a violation in `{function}`, but none was reported")
+            }
+            (Expect::Clean, Err(report)) => {
+                panic!("expected no violation, but got\n{}", describe(&report))
+            }
+            (Expect::Clean, Ok(())) => {}
+        }
+    }
+
+    fn describe(report: &Report) -> String {
+        let mut text = std::format!("{:?} in `{}`\n", report.kind, report.function);
+        for frame in &report.frames {
+            text.push_str("    ");
+            text.push_str(&frame.raw);
+            text.push('\n');
+        }
+        text
+    }
+}
@@ -0,0 +1,253 @@
+//! Passes the reports of the runtime to a Rust handler.
+//!
+//! The runtime writes its reports to a pipe set with
+//! `__sanitizer_set_report_fd`. A thread reads them, copies them to stderr
+//! and calls the handler for each complete report. When the runtime stops the
+//! process after a report, the death callback calls the handler instead, on
+//! the dying thread before the process exits. It reads the rest of the pipe
+//! after the line naming the domain of the violation.
+
+#[cfg(rtsan_enabled)]
+use core::sync::atomic::{AtomicI32, Ordering};
+use std::string::String;
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::report::{self, ViolationKind};
+
+/// A violation reported by the runtime, see [`set_violation_handler`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// The intercepted function, the name of the blocking function, or the
+    /// message of a panic.
+    pub function: String,
+    /// The stack trace, one frame per line as printed by the runtime.
+    pub frames: Vec<String>,
+    /// The [`Domain`](crate::Domain) that was current on the violating
+    /// thread, if the report names it. Reports of intercepted calls only do
+    /// when they stop the process.
+    pub domain: Option<String>,
+}
+
+impl Violation {
+    /// Parses a single report of the runtime, returns `None` if `report`
+    /// has no `ERROR: RealtimeSanitizer:` line.
+    pub fn parse(report: &str) -> Option<Violation> {
+        let report = report::parse(report).into_iter().next()?;
+        Some(Violation {
+            kind: report.kind,
+            function: report.function,
+            frames: report.frames.into_iter().map(|frame| frame.raw).collect(),
+            domain: report.domain,
+        })
+    }
+}
+
+type Handler = fn(&Violation);
+
+static HANDLER: Mutex<Option<Handler>> = Mutex::new(None);
+
+/// Calls `handler` for every violation reported by the runtime, on a
+/// thread of this crate, or on the violating thread right before the runtime
+/// stops the process. The reports are still printed to stderr.
+///
+/// The handler can record violations in telemetry, flush logs before the
+/// process exits, or count violations with `RTSAN_OPTIONS=halt_on_error=false`.
+///
+/// # Example
+///
+/// ```
+/// use rtsan_standalone::*;
+///
+/// fn log_violation(violation: &Violation) {
+///     eprintln!("{:?} in `{}`", violation.kind, violation.function);
+/// }
+///
+/// set_violation_handler(log_violation);
+/// ```
+pub fn set_violation_handler(handler: fn(&Violation)) {
+    let _disabler = crate::ScopedDisabler::default();
+    *HANDLER.lock().unwrap_or_else(|error| error.into_inner()) = Some(handler);
+    #[cfg(rtsan_enabled)]
+    pipe::install();
+}
+
+/// Collects the lines of a report until its `SUMMARY:` line, and the domain
+/// line the death callback may print after it.
+#[cfg_attr(not(rtsan_enabled), allow(dead_code))]
+#[derive(Default)]
+struct Reports {
+    /// Bytes after the last complete line.
+    partial: Vec<u8>,
+    report: String,
+    /// Violations of complete reports, not yet passed to the handler.
+    complete: Vec<Violation>,
+}
+
+#[cfg_attr(not(rtsan_enabled), allow(dead_code))]
+impl Reports {
+    /// Adds the bytes read from the pipe.
+    fn push(&mut self, bytes: &[u8]) {
+        self.partial.extend_from_slice(bytes);
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(domain) = report::parse_domain(&line) {
+                if let Some(violation) = self.complete.last_mut() {
+                    violation.domain.get_or_insert_with(|| domain.into());
+                }
+                continue;
+            }
+            if line.contains("ERROR: RealtimeSanitizer:") {
+                self.report.clear();
+            }
+            self.report.push_str(&line);
+            if line.starts_with("SUMMARY:") {
+                let report = core::mem::take(&mut self.report);
+                self.complete.extend(Violation::parse(&report));
+            }
+        }
+    }
+}
+
+/// Calls the handler for each of `violations`. No lock is held meanwhile, so
+/// the handler may call into this crate again.
+#[cfg_attr(not(rtsan_enabled), allow(dead_code))]
+fn notify(violations: Vec<Violation>) {
+    let handler = *HANDLER.lock().unwrap_or_else(|error| error.into_inner());
+    if let Some(handler) = handler {
+        for violation in &violations {
+            handler(violation);
+        }
+    }
+}
+
+/// Where the runtime writes its reports, stderr unless [`set_report_fd`]
+/// changed it.
+#[cfg(rtsan_enabled)]
+static REPORT_FD: AtomicI32 = AtomicI32::new(libc::STDERR_FILENO);
+
+/// Makes the runtime write its reports to `fd`.
+#[cfg(rtsan_enabled)]
+pub(crate) fn set_report_fd(fd: i32) {
+    REPORT_FD.store(fd, Ordering::Relaxed);
+    unsafe {
+        rtsan_standalone_sys::__sanitizer_set_report_fd(fd as usize as *mut core::ffi::c_void);
+    }
+}
+
+/// Writes `bytes` where the runtime writes its reports, so they stay in
+/// order with them.
+#[cfg(rtsan_enabled)]
+pub(crate) fn write_report(mut bytes: &[u8]) {
+    let fd = REPORT_FD.load(Ordering::Relaxed);
+    while !bytes.is_empty() {
+        let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+        if written <= 0 {
+            return;
+        }
+        bytes = &bytes[written as usize..];
+    }
+}
+
+/// Returns `false` if `RTSAN_OPTIONS` sets `halt_on_error` off.
+#[cfg(rtsan_enabled)]
+pub(crate) fn halts_on_error() -> bool {
+    let options = std::env::var("RTSAN_OPTIONS").unwrap_or_default();
+    !options
+        .split([':', ',', ' '])
+        .any(|option| matches!(option, "halt_on_error=0" | "halt_on_error=false"))
+}
+
+#[cfg(rtsan_enabled)]
+mod pipe {
+    use std::io::{ErrorKind, Write};
+    use std::sync::{Mutex, Once};
+
+    use super::Reports;
+
+    static INSTALL: Once = Once::new();
+    /// The read end of the pipe and the reports read so far.
+    static READER: Mutex<Option<(i32, Reports)>> = Mutex::new(None);
+
+    pub(super) fn install() {
+        INSTALL.call_once(|| {
+            let mut fds = [0; 2];
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+                return;
+            }
+            let [read_fd, write_fd] = fds;
+            unsafe {
+                let flags = libc::fcntl(read_fd, libc::F_GETFL);
+                libc::fcntl(read_fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(read_fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(write_fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+            *READER.lock().unwrap_or_else(|error| error.into_inner()) =
+                Some((read_fd, Reports::default()));
+            // When the runtime stops the process after a report, the death
+            // callback passes it to the handler on the violating thread,
+            // together with its domain.
+            let halts = super::halts_on_error();
+            let spawned = std::thread::Builder::new()
+                .name("rtsan-violations".into())
+                .spawn(move || loop {
+                    let mut poll = libc::pollfd {
+                        fd: read_fd,
+                        events: libc::POLLIN,
+                        revents: 0,
+                    };
+                    if unsafe { libc::poll(&mut poll, 1, -1) } < 0 {
+                        if std::io::Error::last_os_error().kind() == ErrorKind::Interrupted {
+                            continue;
+                        }
+                        // nobody would read the pipe anymore, so the runtime
+                        // writes to stderr again before it fills up
+                        super::set_report_fd(libc::STDERR_FILENO);
+                        drain(true);
+                        return;
+                    }
+                    drain(!halts);
+                });
+            if spawned.is_ok() {
+                super::set_report_fd(write_fd);
+            }
+        });
+    }
+
+    /// Reads everything available from the pipe. With `notify`, calls the
+    /// handler for the complete reports once the pipe is released. Called
+    /// by the reader thread and by the death callback.
+    pub(crate) fn drain(notify: bool) {
+        let violations = {
+            let mut reader = READER.lock().unwrap_or_else(|error| error.into_inner());
+            let Some((fd, reports)) = reader.as_mut() else {
+                return;
+            };
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = unsafe { libc::read(*fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+                if read <= 0 {
+                    break;
+                }
+                let bytes = &buffer[..read as usize];
+                let _ = std::io::stderr().write_all(bytes);
+                reports.push(bytes);
+            }
+            if !notify {
+                return;
+            }
+            core::mem::take(&mut reports.complete)
+        };
+        super::notify(violations);
+    }
+}
+
+/// Runs the handler for the last report before the runtime stops the
+/// process.
+#[cfg(rtsan_enabled)]
+pub(crate) fn on_death() {
+    pipe::drain(true);
+}
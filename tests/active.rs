@@ -0,0 +1,45 @@
+use rtsan_standalone::{
+    ensure_initialized, is_active, is_realtime, nonblocking, realtime_enter, realtime_exit,
+    set_active,
+};
+
+#[nonblocking]
+fn process(data: &[f32]) -> f32 {
+    data.iter().sum()
+}
+
+// flips the process-wide switch, so every check lives in this one test
+#[test]
+fn test_set_active() {
+    ensure_initialized();
+
+    set_active(false);
+    assert!(!is_active());
+    // not sanitized while switched off
+    assert_eq!(process(&[1.0]), 1.0);
+    realtime_enter();
+    assert!(!is_realtime());
+    realtime_exit();
+    set_active(true);
+    assert!(is_active());
+
+    // switching off between enter and exit still leaves the context
+    realtime_enter();
+    set_active(false);
+    realtime_exit();
+    assert!(!is_realtime());
+    set_active(true);
+
+    #[cfg(unix)]
+    {
+        rtsan_standalone::install_signal_switch();
+        // system calls interrupted by the switch are restarted
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        unsafe { libc::sigaction(libc::SIGUSR1, std::ptr::null(), &mut action) };
+        assert_ne!(action.sa_flags & libc::SA_RESTART, 0);
+        unsafe { libc::raise(libc::SIGUSR2) };
+        assert!(!is_active());
+        unsafe { libc::raise(libc::SIGUSR1) };
+        assert!(is_active());
+    }
+}
@@ -0,0 +1,112 @@
+use rtsan_standalone::{
+    blocking, no_sanitize_realtime, nonblocking, realtime, set_backend, Event, Recording, Sanitized,
+};
+
+#[blocking]
+fn load_preset() {}
+
+#[no_sanitize_realtime]
+fn log_value() {}
+
+#[nonblocking]
+fn process(data: &mut [f32]) {
+    data.fill(0.5);
+}
+
+#[nonblocking]
+fn process_and_load(data: &mut [f32]) {
+    log_value();
+    load_preset();
+    data.fill(0.0);
+}
+
+#[test]
+fn test_recording() {
+    set_backend(&Recording);
+    Recording::take();
+
+    let mut data = [0.0; 16];
+    process(&mut data);
+    assert_eq!(
+        Recording::take(),
+        [Event::RealtimeEnter, Event::RealtimeExit]
+    );
+
+    process_and_load(&mut data);
+    assert_eq!(
+        Recording::take(),
+        [
+            Event::RealtimeEnter,
+            Event::Disable,
+            Event::Enable,
+            Event::BlockingCall("backend::load_preset".to_string()),
+            Event::RealtimeExit,
+        ]
+    );
+
+    let sum = realtime!(data.iter().sum::<f32>());
+    assert_eq!(sum, 0.0);
+    let events = Recording::take();
+    assert_eq!(events.first(), Some(&Event::RealtimeEnter));
+    assert_eq!(events.last(), Some(&Event::RealtimeExit));
+
+    // events are recorded per thread
+    std::thread::spawn(|| process(&mut [0.0; 4]))
+        .join()
+        .unwrap();
+    assert!(Recording::take().is_empty());
+
+    // unbalanced calls never reach the backend, the report in debug builds
+    // disables reporting while it prints
+    rtsan_standalone::realtime_exit();
+    rtsan_standalone::enable();
+    let events = Recording::take();
+    let count = |event: Event| events.iter().filter(|e| **e == event).count();
+    assert_eq!(count(Event::RealtimeExit), 0);
+    assert_eq!(count(Event::Enable), count(Event::Disable));
+}
+
+#[nonblocking]
+trait Device {
+    fn read(&self) -> f32;
+
+    #[blocking]
+    fn open(&self);
+
+    #[no_sanitize_realtime]
+    fn log(&self);
+}
+
+struct Microphone;
+
+impl Device for Microphone {
+    fn read(&self) -> f32 {
+        0.5
+    }
+
+    fn open(&self) {}
+
+    fn log(&self) {}
+}
+
+#[test]
+fn test_trait_methods() {
+    set_backend(&Recording);
+    Recording::take();
+
+    // each method is forwarded with its own attribute
+    let device = Sanitized(Microphone);
+    assert_eq!(device.read(), 0.5);
+    assert_eq!(
+        Recording::take(),
+        [Event::RealtimeEnter, Event::RealtimeExit]
+    );
+    device.open();
+    let events = Recording::take();
+    assert!(
+        matches!(&events[..], [Event::BlockingCall(name)] if name.ends_with("::open")),
+        "{events:?}"
+    );
+    device.log();
+    assert_eq!(Recording::take(), [Event::Disable, Event::Enable]);
+}
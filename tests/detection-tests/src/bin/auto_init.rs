@@ -0,0 +1,14 @@
+// check: RealtimeSanitizer: unsafe-library-call
+// check: Intercepted call to real-time unsafe function `malloc` in real-time context!
+use rtsan_standalone::*;
+
+#[nonblocking]
+fn process() {
+    #[rtsan(allow)]
+    let _ = vec![2.0; 256];
+}
+
+// No call to `ensure_initialized`, the runtime is initialized before `main`.
+fn main() {
+    process();
+}
@@ -0,0 +1,13 @@
+// check: Call to blocking function `abs (foreign code)` in real-time context!
+use rtsan_standalone::*;
+
+#[blocking(reason = "foreign code")]
+extern "C" {
+    fn abs(value: core::ffi::c_int) -> core::ffi::c_int;
+}
+
+#[nonblocking]
+fn main() {
+    ensure_initialized();
+    let _ = unsafe { abs(-1) };
+}
@@ -0,0 +1,17 @@
+// check: Call to blocking function `blocking_reason::Sample::load (disk I/O)` in real-time context!
+use rtsan_standalone::*;
+
+struct Sample;
+
+#[blocking(reason = "disk I/O")]
+impl Sample {
+    fn load() -> Self {
+        Sample
+    }
+}
+
+#[nonblocking]
+fn main() {
+    ensure_initialized();
+    let _ = Sample::load();
+}
@@ -0,0 +1,26 @@
+// check: Call to blocking function `domain::open_device` in real-time context!
+// check: real-time domain `motor` was active
+use rtsan_standalone::*;
+
+#[blocking]
+fn log_reading() {}
+
+#[blocking]
+fn open_device() {}
+
+#[nonblocking(domain = "sensors")]
+fn fuse() {
+    log_reading(); // allowed in "sensors"
+}
+
+#[nonblocking(domain = "motor")]
+fn control() {
+    open_device();
+}
+
+fn main() {
+    ensure_initialized();
+    Domain::get("sensors").allow("domain::log_reading");
+    fuse();
+    control();
+}
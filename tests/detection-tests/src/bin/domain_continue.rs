@@ -0,0 +1,27 @@
+// options: halt_on_error=false
+// check: Call to blocking function `domain_continue::open_device` in real-time context!
+// check: real-time domain `motor` was active
+// check: Call to blocking function `domain_continue::read_sensor` in real-time context!
+// check: real-time domain `sensors` was active
+use rtsan_standalone::*;
+
+#[blocking]
+fn open_device() {}
+
+#[blocking]
+fn read_sensor() {}
+
+#[nonblocking(domain = "motor")]
+fn control() {
+    open_device();
+}
+
+#[nonblocking(domain = "sensors")]
+fn fuse() {
+    read_sensor();
+}
+
+fn main() {
+    control();
+    fuse();
+}
@@ -0,0 +1,9 @@
+// check: Call to blocking function `drop of alloc::vec::Vec<f32>` in real-time context!
+use rtsan_standalone::*;
+
+#[nonblocking(drops = "strict")]
+fn main() {
+    ensure_initialized();
+    let data = scoped_disabler!(vec![0.0f32; 256]);
+    drop(data);
+}
@@ -0,0 +1,19 @@
+// kind: panic
+// function: buffer too short
+// check: Panic `buffer too short` at
+// check: process returned -1
+use rtsan_standalone::*;
+
+#[nonblocking(fallback = -1)]
+extern "C" fn process(buffer: *const f32, len: usize) -> i32 {
+    let buffer = unsafe { core::slice::from_raw_parts(buffer, len) };
+    assert!(buffer.len() >= 16, "buffer too short");
+    0
+}
+
+fn main() {
+    ensure_initialized();
+    let buffer = [0.0; 8];
+    let result = process(buffer.as_ptr(), buffer.len());
+    eprintln!("process returned {result}");
+}
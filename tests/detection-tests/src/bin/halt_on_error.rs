@@ -0,0 +1,24 @@
+// options: halt_on_error=false
+// args: --continue
+// kind: blocking-call
+// function: halt_on_error::load
+// frame: halt_on_error::process
+// check: Call to blocking function `halt_on_error::load` in real-time context!
+// check: continued after the violation
+// check-not: RealtimeSanitizer
+use rtsan_standalone::*;
+
+#[blocking]
+fn load() {}
+
+#[nonblocking]
+fn process() {
+    load();
+}
+
+fn main() {
+    process();
+    if std::env::args().any(|arg| arg == "--continue") {
+        eprintln!("continued after the violation");
+    }
+}
@@ -1,4 +1,4 @@
-// check: Call to blocking function `violation` in real-time context!
+// check: Call to blocking function `manual_blocking::violation` in real-time context!
 use rtsan_standalone::*;
 
 #[nonblocking]
@@ -0,0 +1,16 @@
+// kind: panic
+// function: buffer too short
+// check: Panic `buffer too short` at
+// check: panic.rs:9:5 in real-time context!
+use rtsan_standalone::*;
+
+#[nonblocking]
+fn process(buffer: &[f32]) {
+    assert!(buffer.len() >= 16, "buffer too short");
+}
+
+fn main() {
+    ensure_initialized();
+    install_panic_hook();
+    process(&[0.0; 8]);
+}
@@ -0,0 +1,12 @@
+// expect: clean
+// env: RTSAN_ACTIVE=0
+use rtsan_standalone::*;
+
+#[blocking]
+fn load() {}
+
+// Not reported, sanitizing is switched off before `main`.
+#[nonblocking]
+fn main() {
+    load();
+}
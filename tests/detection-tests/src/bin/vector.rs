@@ -4,6 +4,7 @@ use rtsan_standalone::*;
 
 #[nonblocking]
 fn process() {
+    #[rtsan(allow)]
     let _ = vec![2.0; 256];
 }
 
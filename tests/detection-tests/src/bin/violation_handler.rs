@@ -0,0 +1,20 @@
+// check: ERROR: RealtimeSanitizer: unsafe-library-call
+// check: handled unsafe-library-call of `malloc`
+use rtsan_standalone::*;
+
+fn handle(violation: &Violation) {
+    if violation.kind == ViolationKind::UnsafeLibraryCall {
+        eprintln!("handled unsafe-library-call of `{}`", violation.function);
+    }
+}
+
+#[nonblocking]
+fn process() {
+    #[rtsan(allow)]
+    let _ = vec![2.0; 256];
+}
+
+fn main() {
+    set_violation_handler(handle);
+    process();
+}
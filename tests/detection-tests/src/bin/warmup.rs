@@ -0,0 +1,16 @@
+// check: RealtimeSanitizer: unsafe-library-call
+// check: Intercepted call to real-time unsafe function `malloc` in real-time context!
+use rtsan_standalone::*;
+
+// The first call is not sanitized, the second one is.
+#[nonblocking(warmup = 1)]
+fn process(buffer: &mut Vec<f32>, len: usize) {
+    buffer.reserve_exact(len);
+}
+
+fn main() {
+    ensure_initialized();
+    let mut buffer = Vec::new();
+    process(&mut buffer, 256);
+    process(&mut buffer, 512);
+}
@@ -0,0 +1,33 @@
+use rtsan_standalone::{ensure_initialized, is_realtime, nonblocking, Domain, MAX_DOMAINS};
+
+#[nonblocking(domain = "overflow")]
+fn process() -> Option<Domain> {
+    assert!(is_realtime());
+    Domain::current()
+}
+
+// fills the registry of the process, so it runs in its own binary
+#[test]
+fn test_overflow() {
+    ensure_initialized();
+
+    let default = Domain::get("default");
+    assert_eq!(default.name(), "default");
+    let names: Vec<&'static str> = (0..MAX_DOMAINS)
+        .map(|index| &*format!("domain{index}").leak())
+        .collect();
+    for name in &names {
+        assert_eq!(Domain::get(name).name(), *name);
+        assert_ne!(Domain::get(name), default);
+    }
+
+    // further domains fall back to the default domain, without panicking
+    assert_eq!(Domain::get("overflow"), default);
+    assert_eq!(Domain::get("another"), default);
+    let current = process();
+    if cfg!(feature = "std") {
+        assert_eq!(current, Some(default));
+    }
+    default.set_enabled(false);
+    assert!(!Domain::get("overflow").is_enabled());
+}
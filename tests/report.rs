@@ -0,0 +1,153 @@
+use rtsan_standalone::report::{self, Frame, ViolationKind};
+
+const UNSAFE_LIBRARY_CALL: &str = "\
+==283082==ERROR: RealtimeSanitizer: unsafe-library-call
+Intercepted call to real-time unsafe function `calloc` in real-time context!
+    #0 0x55c0c3be8cf2 in calloc /tmp/llvm-project/compiler-rt/lib/rtsan/rtsan_interceptors_posix.cpp:470:34
+    #1 0x55c0c3be4e69 in alloc::alloc::alloc_zeroed::hf760e6484fdf32c8 /rustc/library/alloc/src/alloc.rs:170:14
+    #2 0x55c0c3be2ab4 in _start (target/debug/examples/vector+0x2ab4) (BuildId: adb992a7e560cd00)
+    #3 0x7f3a5c429d8f  (/lib/x86_64-linux-gnu/libc.so.6+0x29d8f)
+SUMMARY: RealtimeSanitizer: unsafe-library-call /rustc/library/alloc/src/alloc.rs:170:14 in alloc::alloc::alloc_zeroed::hf760e6484fdf32c8
+";
+
+#[test]
+fn test_parse() {
+    let reports = report::parse(UNSAFE_LIBRARY_CALL);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.pid, Some(283082));
+    assert_eq!(report.kind, ViolationKind::UnsafeLibraryCall);
+    assert_eq!(report.function, "calloc");
+    assert_eq!(report.kind.to_string(), "unsafe-library-call");
+    assert_eq!(report.frames.len(), 4);
+    assert!(report
+        .summary
+        .as_deref()
+        .unwrap()
+        .ends_with("in alloc::alloc::alloc_zeroed::hf760e6484fdf32c8"));
+
+    let symbolized = &report.frames[1];
+    assert_eq!(symbolized.index, 1);
+    assert_eq!(symbolized.address, 0x55c0c3be4e69);
+    assert_eq!(
+        symbolized.function.as_deref(),
+        Some("alloc::alloc::alloc_zeroed::hf760e6484fdf32c8")
+    );
+    assert_eq!(
+        symbolized.file.as_deref(),
+        Some("/rustc/library/alloc/src/alloc.rs")
+    );
+    assert_eq!((symbolized.line, symbolized.column), (Some(170), Some(14)));
+    assert_eq!(symbolized.module, None);
+
+    let in_module = &report.frames[2];
+    assert_eq!(in_module.function.as_deref(), Some("_start"));
+    assert_eq!(in_module.file, None);
+    assert_eq!(
+        in_module.module.as_deref(),
+        Some("target/debug/examples/vector")
+    );
+    assert_eq!(in_module.offset, Some(0x2ab4));
+
+    let unsymbolized = &report.frames[3];
+    assert_eq!(unsymbolized.function, None);
+    assert_eq!(
+        unsymbolized.module.as_deref(),
+        Some("/lib/x86_64-linux-gnu/libc.so.6")
+    );
+    assert_eq!(unsymbolized.offset, Some(0x29d8f));
+    assert_eq!(
+        unsymbolized.raw,
+        "#3 0x7f3a5c429d8f  (/lib/x86_64-linux-gnu/libc.so.6+0x29d8f)"
+    );
+}
+
+#[test]
+fn test_parse_frame() {
+    let frame = Frame::parse("#0 0x1 in <T as core::ops::Drop>::drop src/lib.rs:7").unwrap();
+    assert_eq!(
+        frame.function.as_deref(),
+        Some("<T as core::ops::Drop>::drop")
+    );
+    assert_eq!(frame.file.as_deref(), Some("src/lib.rs"));
+    assert_eq!((frame.line, frame.column), (Some(7), None));
+
+    // without a location, the whole rest of the line is the function
+    let frame = Frame::parse("#1 0x2 in <T as core::ops::Drop>::drop").unwrap();
+    assert_eq!(
+        frame.function.as_deref(),
+        Some("<T as core::ops::Drop>::drop")
+    );
+    assert_eq!((frame.file, frame.line), (None, None));
+
+    let frame =
+        Frame::parse("#2 0x3 in <T as app::Process>::run (libapp.so+0x1f) (BuildId: ab)").unwrap();
+    assert_eq!(frame.function.as_deref(), Some("<T as app::Process>::run"));
+    assert_eq!(frame.module.as_deref(), Some("libapp.so"));
+    assert_eq!(frame.offset, Some(0x1f));
+
+    let frame = Frame::parse("#3 0x4 in main /src/main.c").unwrap();
+    assert_eq!(frame.function.as_deref(), Some("main"));
+    assert_eq!(frame.file.as_deref(), Some("/src/main.c"));
+
+    assert!(Frame::parse("SUMMARY: RealtimeSanitizer").is_none());
+    assert!(Frame::parse("#x 0x1 in main").is_none());
+}
+
+#[test]
+fn test_parse_interleaved() {
+    let output = "\
+starting
+==1==ERROR: RealtimeSanitizer: blocking-call
+Call to blocking function `app::load` in real-time context!
+log line from another thread
+    #0 0x10 in app::load src/main.rs:4:5
+==2==ERROR: RealtimeSanitizer: something-new
+    #0 0x20 in app::other src/main.rs:9:1
+SUMMARY: RealtimeSanitizer: something-new src/main.rs:9:1 in app::other
+done
+";
+    let reports = report::parse(output);
+    assert_eq!(reports.len(), 2);
+
+    assert_eq!(reports[0].pid, Some(1));
+    assert_eq!(reports[0].kind, ViolationKind::BlockingCall);
+    assert_eq!(reports[0].function, "app::load");
+    assert_eq!(reports[0].frames.len(), 1);
+    assert_eq!(reports[0].summary, None);
+
+    assert_eq!(reports[1].pid, Some(2));
+    assert_eq!(
+        reports[1].kind,
+        ViolationKind::Other("something-new".into())
+    );
+    assert_eq!(reports[1].function, "");
+    assert_eq!(reports[1].frames[0].line, Some(9));
+
+    assert!(report::parse("no reports here").is_empty());
+}
+
+#[test]
+fn test_parse_panic() {
+    let output = "\
+==7==ERROR: RealtimeSanitizer: panic
+Panic `buffer too short` at src/main.rs:9:5 in real-time context!
+SUMMARY: RealtimeSanitizer: panic src/main.rs:9:5
+";
+    let reports = report::parse(output);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].kind, ViolationKind::Panic);
+    assert_eq!(reports[0].kind.to_string(), "panic");
+    assert_eq!(reports[0].function, "buffer too short");
+    assert!(reports[0].frames.is_empty());
+}
+
+#[test]
+fn test_parse_domain() {
+    let output =
+        format!("{UNSAFE_LIBRARY_CALL}RealtimeSanitizer: real-time domain `audio` was active\n");
+    let reports = report::parse(&output);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].domain.as_deref(), Some("audio"));
+    assert_eq!(report::parse(UNSAFE_LIBRARY_CALL)[0].domain, None);
+}
@@ -1,5 +1,12 @@
 use rtsan_standalone::{
-    blocking, ensure_initialized, no_sanitize_realtime, nonblocking, scoped_disabler,
+    blocking, ensure_initialized, no_sanitize_realtime, nonblocking, realtime, scoped_disabler,
+    status, Domain, Sanitized, ScopedSanitizeRealtime, Status,
+};
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
 };
 
 #[nonblocking]
@@ -37,7 +44,8 @@ fn scoped_disabler_function() {
     let my_data2 = not_sanitized_function();
 
     // The implicit drop at the end of a scope triggers the sanitizer.
-    // Explicitly dropping the allocated data prevents this.
+    // Explicitly dropping the allocated data prevents this, as does
+    // `#[nonblocking(drops = "outside")]`.
     scoped_disabler! {
         drop(my_data);
         drop(my_data2);
@@ -51,6 +59,134 @@ fn test_scroped_disabler() {
     scoped_disabler_function();
 }
 
+// The locals are dropped after the real-time context is exited, so no
+// `scoped_disabler!` is needed around their drop.
+#[nonblocking(drops = "outside")]
+fn drops_outside_function(len: usize) -> usize {
+    let data = scoped_disabler!(vec![1.0f32; len]);
+    let mut count = 0;
+    for sample in &data {
+        count += *sample as usize;
+    }
+    count
+}
+
+#[nonblocking]
+fn drop_outside_local() -> usize {
+    #[rtsan(drop_outside)]
+    let data = scoped_disabler!(vec![1u8; 4]);
+    data.len()
+}
+
+// The type of a local may name items of the body, and a local borrowing
+// one that stays inside stays inside as well.
+#[nonblocking(drops = "outside")]
+fn drops_outside_with_items(pair: (Vec<u8>, Vec<u8>)) -> usize {
+    use std::collections::VecDeque;
+    let queue: VecDeque<u8> = VecDeque::new();
+    let (mut first, _second) = pair;
+    let drain = first.drain(..);
+    queue.len() + drain.count()
+}
+
+struct Held<'a>(Vec<&'a f32>);
+
+impl Drop for Held<'_> {
+    fn drop(&mut self) {}
+}
+
+// `held` later borrows `sum`, which borrows a temporary and stays inside, so
+// `held` stays inside too.
+#[nonblocking(drops = "outside")]
+fn drops_outside_later_borrow(data: &[f32]) -> usize {
+    let sum = &data.iter().sum::<f32>();
+    let mut held = Held(Vec::with_capacity(1));
+    held.0.push(sum);
+    held.0.len()
+}
+
+// Explicit drops and replaced values with drop glue are reported in strict
+// mode, locals that are dropped inside emit a warning.
+#[nonblocking(drops = "strict")]
+fn strict_drops(data: &[f32]) -> usize {
+    // assigned from `first`, which stays inside, so `len` does too
+    #[rtsan(allow)]
+    let mut len = data.len();
+    #[rtsan(allow)]
+    let (first, _) = (len, 0);
+    drop(first);
+    len = first;
+    len
+}
+
+// Only the calls after the first two are sanitized.
+#[nonblocking(warmup = 2)]
+fn lazily_sized(buffer: &mut Vec<f32>, len: usize) -> (usize, bool) {
+    if buffer.len() < len {
+        buffer.resize(len, 0.0);
+    }
+    (buffer.len(), rtsan_standalone::is_realtime())
+}
+
+#[test]
+fn test_warmup() {
+    ensure_initialized();
+
+    let mut buffer = Vec::new();
+    assert_eq!(lazily_sized(&mut buffer, 16), (16, false));
+    assert_eq!(lazily_sized(&mut buffer, 32), (32, false));
+    assert_eq!(lazily_sized(&mut buffer, 32), (32, true));
+    assert_eq!(lazily_sized(&mut buffer, 32), (32, true));
+}
+
+// About one in four calls is sanitized.
+#[nonblocking(sample = 1/4)]
+fn sampled_function(data: &[f32]) -> (f32, bool) {
+    (data.iter().sum(), rtsan_standalone::is_realtime())
+}
+
+#[nonblocking(warmup = 1, sample = 1/2)]
+fn sampled_after_warmup() -> u32 {
+    1
+}
+
+#[test]
+fn test_sample() {
+    ensure_initialized();
+
+    let mut entered = 0;
+    for _ in 0..400 {
+        let (sum, realtime) = sampled_function(&[1.0, 2.0]);
+        assert_eq!(sum, 3.0);
+        entered += realtime as usize;
+        assert_eq!(sampled_after_warmup(), 1);
+    }
+    // only sanitized builds sample, `RTSAN_SAMPLE_RATE` may override the rate
+    let sampled = matches!(status(), Status::Enabled { .. })
+        && std::env::var_os("RTSAN_SAMPLE_RATE").is_none();
+    if sampled {
+        assert!(
+            (50..150).contains(&entered),
+            "{entered} of 400 calls entered"
+        );
+    } else {
+        assert_eq!(entered, 400);
+    }
+    let guard = rtsan_standalone::ScopedSanitizeRealtime::sampled(1);
+    assert!(guard.is_some());
+}
+
+#[test]
+fn test_drops() {
+    ensure_initialized();
+
+    assert_eq!(drops_outside_function(8), 8);
+    assert_eq!(drop_outside_local(), 4);
+    assert_eq!(drops_outside_with_items((vec![1, 2], Vec::new())), 2);
+    assert_eq!(drops_outside_later_borrow(&[1.0, 2.0]), 1);
+    assert_eq!(strict_drops(&[0.0; 3]), 3);
+}
+
 #[blocking]
 fn blocking_function() {}
 
@@ -92,3 +228,607 @@ fn test_early_return() {
     let a = early_return(&data);
     assert_eq!(a, Some(data.as_slice()));
 }
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+// minimal single-threaded executor, polls until the future is ready
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+// yields back to the executor once before completing
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[nonblocking]
+async fn async_function(data: &[f32]) -> f32 {
+    YieldOnce(false).await;
+    data.iter().sum()
+}
+
+#[test]
+fn test_async() {
+    ensure_initialized();
+
+    let data = [1.0; 16];
+    // the executor allocates between polls, which is fine outside of `poll`
+    let sum = block_on(async {
+        let a = async_function(&data).await;
+        let b = Box::pin(async_function(&data)).await;
+        a + b
+    });
+    assert_eq!(sum, 32.0);
+}
+
+struct Voice {
+    gain: f32,
+}
+
+#[nonblocking]
+impl Voice {
+    #[rtsan(skip)]
+    fn new() -> Self {
+        Self { gain: 0.5 }
+    }
+
+    fn process(&self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample *= self.gain;
+        }
+    }
+
+    #[no_sanitize_realtime]
+    fn samples(&self) -> Vec<f32> {
+        vec![self.gain; 16]
+    }
+}
+
+#[nonblocking]
+impl Drop for Voice {
+    fn drop(&mut self) {
+        self.gain = 0.0;
+    }
+}
+
+#[nonblocking]
+mod dsp {
+    pub fn sum(data: &[f32]) -> f32 {
+        data.iter().sum()
+    }
+
+    pub struct Gain(pub f32);
+
+    impl Gain {
+        pub fn apply(&self, sample: f32) -> f32 {
+            sample * self.0
+        }
+    }
+
+    #[rtsan(skip)]
+    pub fn create() -> Vec<f32> {
+        vec![1.0; 16]
+    }
+}
+
+#[test]
+fn test_impl_and_mod() {
+    ensure_initialized();
+
+    let voice = Voice::new();
+    let mut buffer = voice.samples();
+    voice.process(&mut buffer);
+    assert_eq!(buffer[0], 0.25);
+
+    let data = dsp::create();
+    assert_eq!(dsp::Gain(2.0).apply(dsp::sum(&data)), 32.0);
+}
+
+#[test]
+fn test_realtime() {
+    ensure_initialized();
+
+    let data = vec![1.0; 16];
+    let sum: f32 = realtime! {
+        let scaled = data.iter().map(|sample| sample * 2.0);
+        scaled.sum()
+    };
+    assert_eq!(sum, 32.0);
+
+    let mut calls = 0;
+    let mut callback = realtime!(|output: &mut [f32]| {
+        calls += 1;
+        output.copy_from_slice(&data);
+    });
+    let mut output = [0.0; 16];
+    callback(&mut output);
+    callback(&mut output);
+    assert_eq!(calls, 2);
+    assert_eq!(output, [1.0; 16]);
+
+    let gain = 0.5;
+    let apply = realtime!(move |sample: f32| -> f32 { sample * gain });
+    assert_eq!(apply(2.0), 1.0);
+}
+
+struct Sample<T> {
+    data: Vec<T>,
+}
+
+#[blocking(reason = "disk I/O")]
+impl<T: Default + Clone> Sample<T> {
+    fn load(len: usize) -> Self {
+        Self {
+            data: vec![T::default(); len],
+        }
+    }
+
+    fn convert<U: From<T>>(&self) -> Vec<U> {
+        self.data.iter().cloned().map(U::from).collect()
+    }
+}
+
+#[blocking(name = "open_file", reason = "file system access")]
+fn open_file() {}
+
+#[test]
+fn test_blocking_arguments() {
+    ensure_initialized();
+
+    let sample = Sample::<f32>::load(16);
+    let converted = sample.convert::<f64>();
+    assert_eq!(converted.len(), 16);
+    open_file();
+}
+
+// the generated code does not resolve through local items named like
+// `core`, `Ok` or `Err`
+mod shadowed {
+    #![allow(dead_code)]
+
+    mod core {}
+    struct Ok;
+    struct Err;
+
+    use rtsan_standalone::{blocking, nonblocking};
+
+    #[nonblocking(fallback = -1)]
+    pub extern "C" fn process(value: i32) -> i32 {
+        value
+    }
+
+    pub struct Voice;
+
+    #[blocking]
+    impl Voice {
+        pub fn load(_path: &str) -> Voice {
+            Voice
+        }
+    }
+
+    #[blocking]
+    pub fn convert<T: Default>() -> T {
+        T::default()
+    }
+}
+
+#[test]
+fn test_hygiene() {
+    let _ = shadowed::Voice::load("voice.wav");
+    assert_eq!(shadowed::convert::<u32>(), 0);
+    assert_eq!(shadowed::process(3), 3);
+}
+
+#[blocking(reason = "foreign code")]
+extern "C" {
+    /// Absolute value from the C library.
+    fn abs(value: std::ffi::c_int) -> std::ffi::c_int;
+    #[link_name = "labs"]
+    pub fn long_abs(_: std::ffi::c_long) -> std::ffi::c_long;
+    #[rtsan(skip)]
+    fn printf(format: *const std::ffi::c_char, ...) -> std::ffi::c_int;
+}
+
+#[blocking(safe)]
+extern "C" {
+    #[link_name = "llabs"]
+    fn safe_abs(value: std::ffi::c_longlong) -> std::ffi::c_longlong;
+}
+
+#[test]
+fn test_blocking_extern() {
+    ensure_initialized();
+
+    assert_eq!(unsafe { abs(-3) }, 3);
+    assert_eq!(unsafe { long_abs(-4) }, 4);
+    assert_eq!(safe_abs(-5), 5);
+    let _ = printf;
+}
+
+// Without `#[rtsan(allow)]` these statements would emit compile-time warnings.
+// They do not allocate, because the capacity is zero.
+#[nonblocking(lint = true)]
+fn allowed_allocation(data: &[f32]) -> usize {
+    #[rtsan(allow)]
+    let empty: Vec<f32> = Vec::with_capacity(0);
+    #[rtsan(allow)]
+    let filtered: Vec<f32> = data.iter().copied().filter(|_| false).collect();
+    empty.len() + filtered.len()
+}
+
+// Without `lint = true` nothing is reported, `#[rtsan(allow)]` is still accepted.
+#[nonblocking]
+fn unlinted_allocation(data: &[f32]) -> usize {
+    let empty: Vec<f32> = Vec::with_capacity(0);
+    #[rtsan(allow)]
+    let filtered: Vec<f32> = data.iter().copied().filter(|_| false).collect();
+    empty.len() + filtered.len()
+}
+
+// `#[rtsan(allow)]` is removed from statements of every kind.
+#[nonblocking(lint = true)]
+fn allowed_statements(data: &mut [f32], state: &std::sync::Mutex<f32>) -> f32 {
+    #[rtsan(allow)]
+    data[0] = *state.lock().unwrap();
+    #[rtsan(allow)]
+    (data.to_vec().len());
+    #[rtsan(allow)]
+    return data[0];
+}
+
+#[nonblocking(lint = true)]
+fn allowed_closure(data: &[f32]) -> impl Fn() -> usize + '_ {
+    #[rtsan(allow)]
+    move || data.to_vec().len()
+}
+
+#[test]
+fn test_lint_allow() {
+    ensure_initialized();
+
+    assert_eq!(allowed_allocation(&[1.0; 16]), 0);
+    assert_eq!(unlinted_allocation(&[1.0; 16]), 0);
+    let state = std::sync::Mutex::new(2.0);
+    assert_eq!(allowed_statements(&mut [0.0; 4], &state), 2.0);
+    assert_eq!(allowed_closure(&[1.0; 4])(), 4);
+
+    let len = realtime! {
+        lint = true;
+        crate = ::rtsan_standalone;
+        #[rtsan(allow)]
+        let empty: Vec<f32> = Vec::with_capacity(0);
+        empty.len()
+    };
+    assert_eq!(len, 0);
+}
+
+#[nonblocking(fallback = -1)]
+extern "C" fn checked_callback(value: i32) -> i32 {
+    if value < 0 {
+        panic!("negative value");
+    }
+    value * 2
+}
+
+#[nonblocking(fallback = ())]
+extern "C" fn unit_callback(value: &mut i32) {
+    *value += 1;
+    assert!(*value < 2, "called twice");
+}
+
+// Without a `fallback` the return type needs no `Default`, a panic aborts.
+#[nonblocking]
+extern "C" fn first_sample(buffer: *mut f32) -> *mut f32 {
+    buffer
+}
+
+#[test]
+fn test_extern_c_panic() {
+    ensure_initialized();
+
+    assert_eq!(checked_callback(4), 8);
+    assert_eq!(checked_callback(-4), -1);
+
+    let mut value = 0;
+    unit_callback(&mut value);
+    unit_callback(&mut value);
+    assert_eq!(value, 2);
+
+    let mut buffer = [0.0f32; 4];
+    assert_eq!(first_sample(buffer.as_mut_ptr()), buffer.as_mut_ptr());
+}
+
+#[nonblocking]
+fn checked_process(value: i32) -> i32 {
+    assert!(value >= 0, "negative value");
+    value * 2
+}
+
+#[test]
+fn test_panic_hook() {
+    ensure_initialized();
+    rtsan_standalone::install_panic_hook();
+
+    assert_eq!(checked_process(2), 4);
+    // the report stops a sanitized process, see the `panic` detection test
+    if matches!(status(), Status::Enabled { .. }) {
+        return;
+    }
+    let result = std::panic::catch_unwind(|| checked_process(-2));
+    assert!(result.is_err());
+    // re-enabled once the panic left the real-time context
+    assert!(!rtsan_standalone::is_disabled());
+    assert!(!rtsan_standalone::is_realtime());
+}
+
+#[nonblocking]
+trait Processor {
+    fn process(&mut self, buffer: &mut [f32]);
+
+    fn gain(&self) -> f32 {
+        1.0
+    }
+
+    #[rtsan(skip)]
+    fn name(&self) -> String;
+}
+
+struct Amplifier(f32);
+
+impl Processor for Amplifier {
+    fn process(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample *= self.gain();
+        }
+    }
+
+    fn gain(&self) -> f32 {
+        self.0
+    }
+
+    fn name(&self) -> String {
+        String::from("amplifier")
+    }
+}
+
+// Records whether `process` ran in a real-time context.
+struct Probe(bool);
+
+impl Processor for Probe {
+    fn process(&mut self, _buffer: &mut [f32]) {
+        self.0 = rtsan_standalone::is_realtime();
+    }
+
+    fn name(&self) -> String {
+        String::from("probe")
+    }
+}
+
+#[nonblocking]
+trait Source<T>: Send {
+    unsafe fn read(&self, index: usize) -> T;
+}
+
+impl Source<f32> for Vec<f32> {
+    unsafe fn read(&self, index: usize) -> f32 {
+        *self.get_unchecked(index)
+    }
+}
+
+// Only the default body is sanitized, `Sanitized` can not forward `Clone`.
+#[nonblocking]
+#[rtsan(defaults_only)]
+trait Duplicate: Clone {
+    fn level(&self) -> bool {
+        rtsan_standalone::is_realtime()
+    }
+
+    fn duplicate(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Duplicate for Amplifier {}
+
+impl Clone for Amplifier {
+    fn clone(&self) -> Self {
+        Self(self.0)
+    }
+}
+
+#[test]
+fn test_trait() {
+    ensure_initialized();
+
+    let mut processors: Vec<Box<dyn Processor + Send>> = vec![Box::new(Sanitized(Amplifier(2.0)))];
+    let mut buffer = [1.0; 16];
+    for processor in processors.iter_mut() {
+        processor.process(&mut buffer);
+    }
+    assert_eq!(buffer, [2.0; 16]);
+    assert_eq!(processors[0].name(), "amplifier");
+
+    // only the wrapped implementation is sanitized, the plain one is not annotated
+    let mut wrapped = Sanitized(Probe(false));
+    let mut plain = Probe(true);
+    for processor in [&mut wrapped as &mut dyn Processor, &mut plain] {
+        processor.process(&mut buffer);
+    }
+    assert!(wrapped.0 .0);
+    assert!(!plain.0);
+
+    let source: &dyn Source<f32> = &Sanitized(buffer.to_vec());
+    assert_eq!(unsafe { source.read(3) }, 2.0);
+
+    let voice = Amplifier(0.5).duplicate();
+    assert_eq!(voice.0, 0.5);
+    assert!(voice.level());
+}
+
+// A facade crate re-exporting rtsan-standalone under another path.
+mod facade {
+    pub use rtsan_standalone as rtsan;
+}
+
+#[nonblocking(crate = crate::facade::rtsan)]
+fn facade_nonblocking(data: &[f32]) -> f32 {
+    data.iter().sum()
+}
+
+#[blocking(crate = crate::facade::rtsan, reason = "facade")]
+fn facade_blocking() {}
+
+#[no_sanitize_realtime(crate = crate::facade::rtsan)]
+fn facade_no_sanitize() {}
+
+#[test]
+fn test_crate_path() {
+    ensure_initialized();
+
+    assert_eq!(facade_nonblocking(&[1.0, 2.0]), 3.0);
+    facade_blocking();
+    facade_no_sanitize();
+    let sum = realtime! {
+        crate = crate::facade::rtsan;
+        1 + 2
+    };
+    assert_eq!(sum, 3);
+}
+
+#[blocking]
+fn log_reading() {}
+
+#[nonblocking(domain = "sensors")]
+fn fuse(data: &[f32]) -> f32 {
+    log_reading(); // allowed in "sensors"
+    data.iter().sum()
+}
+
+#[nonblocking(domain = "motor", warmup = 1)]
+fn control(state: &mut Vec<f32>) {
+    state.resize(4, 0.0); // ok on the first call
+}
+
+#[test]
+fn test_domain() {
+    ensure_initialized();
+
+    let sensors = Domain::get("sensors");
+    assert_eq!(sensors.name(), "sensors");
+    assert!(sensors.allow("rtsan_macros::log_reading"));
+    assert!(sensors.allows(b"rtsan_macros::log_reading"));
+    assert!(!sensors.allows(b"rtsan_macros::open_file"));
+    assert_eq!(fuse(&[1.0, 2.0]), 3.0);
+    assert_eq!(Domain::current(), None);
+
+    let motor = Domain::get("motor");
+    assert_ne!(motor, sensors);
+    let mut state = Vec::new();
+    control(&mut state);
+    motor.set_sample_rate(4);
+    assert_eq!(motor.sample_rate(), 4);
+    motor.set_enabled(false);
+    assert!(!motor.is_enabled());
+    {
+        let _guard = ScopedSanitizeRealtime::for_domain("motor");
+        // not sanitized while the domain is disabled
+        assert_eq!(Domain::current(), None);
+        state.push(1.0);
+    }
+    control(&mut state);
+    motor.set_enabled(true);
+    motor.set_sample_rate(1);
+}
+
+struct Mixer;
+
+#[nonblocking]
+impl Mixer {
+    fn mix(&self) -> Option<&'static str> {
+        scoped_disabler! {
+            assert!(rtsan_standalone::is_disabled());
+            rtsan_standalone::current_scope()
+        }
+    }
+}
+
+#[nonblocking]
+fn outer_scope() -> (usize, Option<&'static str>, Option<&'static str>) {
+    let inner = Mixer.mix();
+    (
+        rtsan_standalone::realtime_depth(),
+        rtsan_standalone::current_scope(),
+        inner,
+    )
+}
+
+#[test]
+fn test_context() {
+    ensure_initialized();
+    assert!(!rtsan_standalone::is_realtime());
+    assert!(!rtsan_standalone::is_disabled());
+    assert_eq!(rtsan_standalone::current_scope(), None);
+
+    let (depth, outer, inner) = outer_scope();
+    assert_eq!(depth, 1);
+    assert_eq!(outer, Some("rtsan_macros::outer_scope"));
+    assert_eq!(inner, Some("rtsan_macros::Mixer::mix"));
+    assert_eq!(rtsan_standalone::realtime_depth(), 0);
+    assert!(!rtsan_standalone::is_disabled());
+    assert_eq!(rtsan_standalone::current_scope(), None);
+
+    let depth = realtime!(rtsan_standalone::realtime_depth());
+    assert_eq!(depth, 1);
+}
+
+#[test]
+fn test_unbalanced_calls() {
+    ensure_initialized();
+
+    // reported and not passed to the runtime in debug builds, the state of
+    // the thread stays balanced in every build
+    rtsan_standalone::enable();
+    rtsan_standalone::realtime_exit();
+    assert!(!rtsan_standalone::is_disabled());
+    assert_eq!(rtsan_standalone::realtime_depth(), 0);
+
+    let disabler = rtsan_standalone::ScopedDisabler::default();
+    assert!(rtsan_standalone::is_disabled());
+    drop(disabler);
+    assert!(!rtsan_standalone::is_disabled());
+}
+
+#[test]
+fn test_status() {
+    match status() {
+        Status::Enabled { .. } => rtsan_standalone::require_enabled(),
+        Status::DisabledByEnvironment => {
+            assert!(status().to_string().contains("RTSAN_ENABLE"));
+        }
+        Status::UnsupportedTarget(target) => assert!(!target.is_empty()),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,31 @@
+use rtsan_standalone::{ensure_initialized, is_realtime, nonblocking, status, Domain, Status};
+
+#[nonblocking(domain = "sampled")]
+fn process() -> bool {
+    is_realtime()
+}
+
+#[nonblocking(sample = 1/4)]
+fn sampled() -> bool {
+    is_realtime()
+}
+
+// sets `RTSAN_SAMPLE_RATE` before it is read, so it runs in its own binary
+#[test]
+fn test_domain_rate_wins() {
+    std::env::set_var("RTSAN_SAMPLE_RATE", "1");
+    ensure_initialized();
+
+    Domain::get("sampled").set_sample_rate(4);
+    let entered = (0..400).filter(|_| process()).count();
+    if matches!(status(), Status::Enabled { .. }) {
+        assert!(
+            (50..150).contains(&entered),
+            "{entered} of 400 calls entered"
+        );
+    } else {
+        assert_eq!(entered, 400);
+    }
+    // the variable still overrides the rate of `sample`
+    assert_eq!((0..400).filter(|_| sampled()).count(), 400);
+}
@@ -0,0 +1,63 @@
+use rtsan_standalone::report::ViolationKind;
+use rtsan_standalone::testing::catch_violation;
+use rtsan_standalone::{blocking, status, Status};
+
+#[blocking]
+fn load_preset() -> u32 {
+    7
+}
+
+#[test]
+fn test_catch_violation() {
+    assert_eq!(catch_violation(|| 1 + 2), Ok(3));
+    // allocated before the child enters the real-time context
+    let strings = vec![String::from("a"); 2];
+    assert_eq!(
+        catch_violation(move || strings),
+        Ok(vec![String::from("a"); 2])
+    );
+
+    let result = catch_violation(load_preset);
+    if matches!(status(), Status::Enabled { .. }) {
+        let report = result.unwrap_err();
+        assert_eq!(report.kind, ViolationKind::BlockingCall);
+        assert_eq!(report.function, "testing::load_preset");
+    } else {
+        assert_eq!(result, Ok(7));
+    }
+}
+
+#[test]
+#[should_panic(expected = "without a value or a report")]
+fn test_catch_violation_panic() {
+    // the message is formatted on the panic path, which is not reported
+    let result = catch_violation(|| -> u32 { panic!("in the child {}", 7) });
+    panic!("expected the child to panic, got {result:?}");
+}
+
+#[rtsan_standalone::testing::test(expect_violation = "testing::load_preset")]
+fn test_expect_violation() {
+    load_preset();
+}
+
+#[rtsan_standalone::testing::test(expect_clean)]
+fn test_expect_clean() {
+    let mut data = [0.0f32; 16];
+    data.fill(0.5);
+}
+
+#[rtsan_standalone::testing::test(expect_clean)]
+#[should_panic(expected = "expected no violation")]
+fn test_expect_clean_fails() {
+    load_preset();
+}
+
+mod glob {
+    use rtsan_standalone::*;
+
+    // `testing::test` is not at the crate root, which would make this ambiguous
+    #[test]
+    fn test_builtin() {
+        assert!(!is_realtime());
+    }
+}
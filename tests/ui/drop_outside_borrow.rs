@@ -0,0 +1,11 @@
+use rtsan_standalone::*;
+
+#[nonblocking]
+fn process(pair: (Vec<f32>, Vec<f32>)) -> usize {
+    let (mut first, _second) = pair;
+    #[rtsan(drop_outside)]
+    let drain = first.drain(..);
+    drain.count()
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use rtsan_standalone::*;
+
+#[nonblocking]
+fn process() -> usize {
+    use std::collections::VecDeque;
+    #[rtsan(drop_outside)]
+    let queue: VecDeque<u8>;
+    queue = VecDeque::new();
+    queue.len()
+}
+
+fn main() {}
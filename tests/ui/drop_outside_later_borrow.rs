@@ -0,0 +1,14 @@
+use rtsan_standalone::*;
+
+struct Held<'a>(Vec<&'a f32>);
+
+#[nonblocking]
+fn process(data: &[f32]) -> usize {
+    let sum = &data.iter().sum::<f32>();
+    #[rtsan(drop_outside)]
+    let mut held = Held(Vec::new());
+    held.0.push(sum);
+    held.0.len()
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rtsan_standalone::*;
+
+#[nonblocking(fallback = 3)]
+pub async fn process() -> u32 {
+    1
+}
+
+fn main() {}
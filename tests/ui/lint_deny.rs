@@ -0,0 +1,12 @@
+#![deny(deprecated)]
+
+use rtsan_standalone::*;
+
+#[nonblocking(lint = true)]
+fn process(data: &[f32]) -> Vec<f32> {
+    data.to_vec()
+}
+
+fn main() {
+    process(&[0.0; 16]);
+}
@@ -0,0 +1,8 @@
+use rtsan_standalone::*;
+
+trait Processor {
+    #[nonblocking]
+    fn process(&mut self, buffer: &mut [f32]);
+}
+
+fn main() {}
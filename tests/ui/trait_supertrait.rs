@@ -0,0 +1,8 @@
+use rtsan_standalone::*;
+
+#[nonblocking]
+trait Processor: Clone {
+    fn process(&mut self, buffer: &mut [f32]);
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+#![cfg(unix)]
+
+use rtsan_standalone::{set_violation_handler, Violation, ViolationKind};
+
+#[test]
+fn test_violation() {
+    let report = "\
+==283082==ERROR: RealtimeSanitizer: unsafe-library-call
+Intercepted call to real-time unsafe function `calloc` in real-time context!
+    #0 0x55c0c3be8cf2 in calloc rtsan_interceptors_posix.cpp:470:34
+    #1 0x55c0c3be4e69 in alloc::alloc::alloc_zeroed alloc.rs:170:14
+SUMMARY: RealtimeSanitizer: unsafe-library-call alloc.rs:170:14 in alloc::alloc::alloc_zeroed
+";
+    let violation = Violation::parse(report).unwrap();
+    assert_eq!(violation.kind, ViolationKind::UnsafeLibraryCall);
+    assert_eq!(violation.function, "calloc");
+    assert_eq!(violation.frames.len(), 2);
+    assert!(violation.frames[1].starts_with("#1 0x55c0c3be4e69"));
+
+    let report = "\
+==1==ERROR: RealtimeSanitizer: blocking-call
+Call to blocking function `my_crate::Sample::load (disk I/O)` in real-time context!
+";
+    let violation = Violation::parse(report).unwrap();
+    assert_eq!(violation.kind, ViolationKind::BlockingCall);
+    assert_eq!(violation.function, "my_crate::Sample::load (disk I/O)");
+    assert!(Violation::parse("no report").is_none());
+
+    // replaces the handler of the whole process
+    set_violation_handler(|violation| eprintln!("{violation:?}"));
+}